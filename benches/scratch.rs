@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright © 2024 Jaxydog
+//
+// This file is part of Amethyst Colorizer.
+//
+// Amethyst Colorizer is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General
+// Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any
+// later version.
+//
+// Amethyst Colorizer is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU Affero General Public License for more
+// details.
+//
+// You should have received a copy of the GNU Affero General Public License along with Amethyst Colorizer. If not, see
+// <https://www.gnu.org/licenses/>.
+
+//! Compares the number of allocations and time taken when transforming many images with and without a reusable
+//! scratch buffer.
+//!
+//! Run with `cargo bench --bench scratch`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use amethyst_colorizer::config::{DyeColorConfig, Filter};
+use amethyst_colorizer::Scratch;
+
+/// The number of times that each image is transformed.
+const ITERATIONS: u32 = 200;
+
+/// Wraps the system allocator, counting every allocation.
+struct Counting;
+
+/// The number of allocations made since the program started.
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+// SAFETY: Every call is forwarded directly to the system allocator.
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+
+        // SAFETY: The caller upholds the contract of `GlobalAlloc::alloc`.
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: The caller upholds the contract of `GlobalAlloc::dealloc`.
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+
+        // SAFETY: The caller upholds the contract of `GlobalAlloc::realloc`.
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Runs the given transformation on a copy of the base image for every iteration, returning the number of allocations
+/// made and the time taken by the transformations alone.
+fn measure(
+    base: &image::RgbaImage,
+    mut transform: impl FnMut(&mut image::RgbaImage) -> amethyst_colorizer::Result<()>,
+) -> amethyst_colorizer::Result<(usize, Duration)> {
+    let mut image = base.clone();
+    let mut elapsed = Duration::ZERO;
+    let mut allocations = 0;
+
+    for _ in 0 .. ITERATIONS {
+        image.copy_from_slice(base);
+
+        let (start, before) = (Instant::now(), ALLOCATIONS.load(Ordering::Relaxed));
+
+        transform(&mut image)?;
+
+        allocations += ALLOCATIONS.load(Ordering::Relaxed) - before;
+        elapsed += start.elapsed();
+    }
+
+    Ok((allocations, elapsed))
+}
+
+fn main() -> amethyst_colorizer::Result<()> {
+    let base = amethyst_colorizer::default_base_image()?;
+    let mut config = DyeColorConfig::new([137, 50, 184]);
+
+    config.filters = [Filter::image_blur_set(1.0), Filter::image_contrast_add(10.0)].into();
+    config.opaque_bounds_only = true;
+    config.lock = [amethyst_colorizer::config::FilterTarget::Brightness].into();
+
+    let (fresh, fresh_time) = self::measure(&base, |image| amethyst_colorizer::transform_image(&config, image))?;
+    let mut scratch = Scratch::new();
+    let (reused, reused_time) =
+        self::measure(&base, |image| amethyst_colorizer::transform_image_with_scratch(&config, image, &mut scratch))?;
+
+    println!("{:<8} {:>12} {:>12}", "", "allocations", "time/image");
+    println!("{:<8} {:>12} {:>12.2?}", "fresh", fresh, fresh_time / ITERATIONS);
+    println!("{:<8} {:>12} {:>12.2?}", "scratch", reused, reused_time / ITERATIONS);
+
+    Ok(())
+}
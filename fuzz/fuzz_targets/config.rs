@@ -0,0 +1,46 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright © 2024 Jaxydog
+//
+// This file is part of Amethyst Colorizer.
+//
+// Amethyst Colorizer is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General
+// Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any
+// later version.
+//
+// Amethyst Colorizer is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU Affero General Public License for more
+// details.
+//
+// You should have received a copy of the GNU Affero General Public License along with Amethyst Colorizer. If not, see
+// <https://www.gnu.org/licenses/>.
+
+#![no_main]
+
+use amethyst_colorizer::config::Config;
+use amethyst_colorizer::Error;
+use image::{Rgba, RgbaImage};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(config) = serde_json::from_slice::<Config>(data) else { return };
+
+    if config.validate().is_err() {
+        return;
+    }
+
+    for config in config.colors.values() {
+        let target = config.target_hsv();
+
+        assert!(target.hue.into_degrees().is_finite(), "validated target hue is not finite");
+        assert!(target.saturation.is_finite() && target.value.is_finite(), "validated target is not finite");
+
+        let mut image = RgbaImage::from_pixel(2, 2, Rgba([155, 95, 219, 255]));
+
+        // Invalid filter combinations are allowed to error, they're just not allowed to panic or to see a non-finite
+        // value that validation should have rejected.
+        let result = amethyst_colorizer::transform_image(config, &mut image);
+
+        assert!(!matches!(result, Err(Error::NonFiniteValue(..))), "validated filter has a non-finite value");
+    }
+});
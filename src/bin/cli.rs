@@ -19,17 +19,39 @@
 #![warn(clippy::nursery, clippy::todo, clippy::pedantic)]
 #![allow(clippy::module_name_repetitions)]
 
-use std::path::Path;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ffi::OsString;
+use std::io::{Cursor, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 
-use amethyst_colorizer::config::{Config, DyeColor};
+use amethyst_colorizer::config::{BitDepth, Config, DistanceMetric, DyeColor, DyeColorConfig};
 use anyhow::{bail, Result};
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use image::{ColorType, DynamicImage, GenericImageView, GrayImage, ImageFormat, Luma, RgbaImage};
+use palette::{FromColor, Hsv, Srgb};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use zip::write::FileOptions;
+use zip::result::ZipError;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
 
+// Each flag is an independent command-line switch, so grouping them would only make them harder to find.
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Clone, Debug, Parser)]
 #[command(author, version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub struct Arguments {
+    /// An optional subcommand to run instead of converting an image.
+    #[command(subcommand)]
+    pub command: Option<Command>,
     /// The path of the image to convert.
-    pub path: Box<Path>,
+    #[arg(required_unless_present_any = ["use_default_base", "print_effective_hue"])]
+    pub path: Option<Box<Path>>,
     /// The path of the color configuration to load.
     #[arg(short = 'c', long = "config", value_name = "PATH", default_value = "./res/default.json")]
     pub config: Box<Path>,
@@ -39,6 +61,439 @@ pub struct Arguments {
     /// The directory to output the converted files into.
     #[arg(short = 'o', long = "output-dir", value_name = "DIR", default_value = "./out/")]
     pub output: Box<Path>,
+    /// The seed used by noise filters, overriding any seeds set within the configuration.
+    #[arg(long = "seed")]
+    pub seed: Option<u64>,
+    /// Convert the built-in amethyst texture instead of an input file.
+    #[arg(long = "use-default-base", conflicts_with = "path")]
+    pub use_default_base: bool,
+    /// Place each color's outputs within its own subdirectory of the output directory.
+    #[arg(long = "group-by-color")]
+    pub group_by_color: bool,
+    /// Print failures to stderr as JSON objects rather than as human-readable text.
+    #[arg(long = "json-errors")]
+    pub json_errors: bool,
+    /// The path of a reference image whose brightness distribution each output should be matched to.
+    #[arg(long = "match-histogram", value_name = "PATH")]
+    pub histogram_reference: Option<Box<Path>>,
+    /// The path of a grayscale mask image, where white is fully converted and black is left as-is.
+    #[arg(long = "mask", value_name = "PATH")]
+    pub mask: Option<Box<Path>>,
+    /// Write a grayscale image containing the alpha channel of each output alongside it.
+    #[arg(long = "emit-alpha-mask")]
+    pub emit_alpha_mask: bool,
+    /// Write a grayscale image for each of the red, green, blue, and alpha channels of each output alongside it.
+    #[arg(long = "split-channels")]
+    pub split_channels: bool,
+    /// Write every mipmap level of each output alongside it, halving its size each time until it is a single pixel.
+    #[arg(long = "mipmaps")]
+    pub mipmaps: bool,
+    /// Skip writing any output that is identical to the input image.
+    #[arg(long = "skip-unchanged")]
+    pub skip_unchanged: bool,
+    /// Print the average color of each generated variant.
+    #[arg(long = "report-colors")]
+    pub report_colors: bool,
+    /// Only convert the given region of the image, formatted as 'X,Y,WIDTH,HEIGHT'.
+    #[arg(long = "region", value_name = "X,Y,W,H")]
+    pub region: Option<Region>,
+    /// How the converted files are written into the output directory.
+    #[arg(long = "output-format", value_name = "FORMAT", default_value = "png")]
+    pub output_format: OutputFormat,
+    /// Print the number of processed images and pixels, along with throughput rates, after the run.
+    #[arg(long = "stats")]
+    pub stats: bool,
+    /// The width in pixels of a border of each output's dye color to draw around it.
+    #[arg(long = "swatch-border", value_name = "PX", default_value_t = 0)]
+    pub swatch_border: u32,
+    /// Scale the brightness of each output towards the given mean perceived luminance, from 0.0 to 1.0.
+    #[arg(long = "balance-luminance", value_name = "TARGET")]
+    pub target_luminance: Option<f32>,
+    /// Blend every pixel towards each color's RGB value by the given factor, rather than replacing its hue.
+    #[arg(long = "force-rgb", value_name = "FACTOR")]
+    pub force_rgb: Option<f32>,
+    /// The metric used to compare colors when matching them, which overrides every color's configured metric.
+    #[arg(long = "palette-distance", value_name = "METRIC")]
+    pub palette_distance: Option<DistanceMetric>,
+    /// Override a color's RGB value, formatted as 'COLOR=#RRGGBB'. May be given multiple times.
+    #[arg(long = "set-color", value_name = "COLOR=HEX")]
+    pub set_colors: Vec<ColorOverride>,
+    /// Write an unchanged copy of the input image alongside the converted images.
+    #[arg(long = "include-source")]
+    pub include_source: bool,
+    /// The format that each converted image is encoded as.
+    #[arg(long = "format", value_name = "FORMAT", default_value = "png")]
+    pub image_format: ImageEncoding,
+    /// Write a single image containing every converted image laid out in a grid.
+    #[arg(long = "sheet")]
+    pub sheet: bool,
+    /// Label each image within the contact sheet with its color's name.
+    #[arg(long = "labels", requires = "sheet")]
+    pub labels: bool,
+    /// Generate a palette of hues using the given scheme and convert the image into each, ignoring dye colors.
+    #[arg(long = "seed-palette", value_name = "SCHEME")]
+    pub seed_palette: Option<PaletteScheme>,
+    /// The number of hues generated by the 'even' palette scheme.
+    #[arg(long = "palette-count", value_name = "N", default_value_t = 12, requires = "seed_palette")]
+    pub palette_count: u32,
+    /// The hue, in degrees, that generated palettes start from.
+    #[arg(long = "palette-base-hue", value_name = "DEGREES", default_value_t = 0.0, requires = "seed_palette")]
+    pub palette_base_hue: f32,
+    /// Treat the input image as already being in linear light, rather than sRGB.
+    ///
+    /// Use this for textures exported from pipelines that work in linear light, such as some renderers, so that each
+    /// color's hue is matched within the same space. The output is left in linear light.
+    #[arg(long = "assume-linear")]
+    pub assume_linear: bool,
+    /// Print the hue, saturation, and value that each color recolors images towards, without converting any image.
+    #[arg(long = "print-effective-hue")]
+    pub print_effective_hue: bool,
+    /// Apply each color's filters without replacing the image's hue.
+    ///
+    /// This is useful for previewing the effect of filters on the base image, and any configured RGB value is ignored.
+    #[arg(long = "no-hue")]
+    pub no_hue: bool,
+    /// The directory to write an image into after the hue is replaced and after each filter is applied.
+    #[arg(long = "dump-stages", value_name = "DIR", requires = "color")]
+    pub dump_stages: Option<Box<Path>>,
+    /// Fail if the input image's dimensions differ from the given size, formatted as `WIDTHxHEIGHT`.
+    #[arg(long = "expect-size", value_name = "WxH")]
+    pub expect_size: Option<Size>,
+    /// Decode the input image as the given format, regardless of its extension or contents.
+    #[arg(long = "input-format", value_name = "FORMAT", conflicts_with = "use_default_base")]
+    pub input_format: Option<InputFormat>,
+    /// Write a JSON file describing the whole run, including every produced output, to the given path.
+    #[arg(long = "summary-json", value_name = "PATH")]
+    pub summary_json: Option<Box<Path>>,
+    /// Store the given gamma within each written PNG file, such as '0.45455' for sRGB or '1.0' for linear light.
+    ///
+    /// Only the file's metadata is changed, allowing tools that respect it to interpret the pixel data correctly.
+    #[arg(long = "gamma", value_name = "GAMMA")]
+    pub gamma: Option<f32>,
+    /// Open the output in the default viewer once finished.
+    ///
+    /// A single converted image is opened directly, otherwise the contact sheet or output directory is opened instead.
+    /// This does nothing if the standard output is not a terminal or when running within CI.
+    #[arg(long = "open")]
+    pub open: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum InputFormat {
+    /// Decode the input as a PNG file.
+    Png,
+    /// Decode the input as a TGA file.
+    Tga,
+}
+
+impl InputFormat {
+    #[must_use]
+    pub const fn format(self) -> ImageFormat {
+        match self {
+            Self::Png => ImageFormat::Png,
+            Self::Tga => ImageFormat::Tga,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum PaletteScheme {
+    /// Evenly-spaced hues around the color wheel.
+    Even,
+    /// The base hue and its opposite.
+    Complementary,
+    /// Three hues evenly spaced from the base hue.
+    Triadic,
+}
+
+impl PaletteScheme {
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn hues(self, base: f32, count: u32) -> Vec<f32> {
+        let count = match self {
+            Self::Even => count.max(1),
+            Self::Complementary => 2,
+            Self::Triadic => 3,
+        };
+
+        (0 .. count).map(|index| (base + (360.0 * index as f32 / count as f32)).rem_euclid(360.0)).collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ImageEncoding {
+    /// Encode images as PNG files.
+    Png,
+    /// Encode images as TGA files, which only support 8-bit channels.
+    Tga,
+    /// Encode every converted image as a frame of a single animated PNG, cycling through each color.
+    Apng,
+    /// Encode every converted image as a frame of a single animated WebP, which is not yet supported.
+    Awebp,
+}
+
+impl ImageEncoding {
+    #[must_use]
+    pub const fn format(self) -> ImageFormat {
+        match self {
+            Self::Png | Self::Apng => ImageFormat::Png,
+            Self::Tga => ImageFormat::Tga,
+            Self::Awebp => ImageFormat::WebP,
+        }
+    }
+
+    #[must_use]
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Png | Self::Apng => "png",
+            Self::Tga => "tga",
+            Self::Awebp => "webp",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Write each image as a separate file.
+    Png,
+    /// Bundle every image into a single zip archive.
+    Zip,
+    /// Bundle every image into a single gzipped tarball.
+    Targz,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum Command {
+    /// Print the semantic differences between two color configurations.
+    DiffConfig {
+        /// The path of the original configuration.
+        old: Box<Path>,
+        /// The path of the updated configuration.
+        new: Box<Path>,
+    },
+    /// Create a color configuration by sampling each dye color from a grid of swatches.
+    PaletteFromImage {
+        /// The path of the swatch image.
+        image: Box<Path>,
+        /// The number of swatch columns.
+        #[arg(long = "columns", default_value_t = 4)]
+        columns: u32,
+        /// The number of swatch rows.
+        #[arg(long = "rows", default_value_t = 4)]
+        rows: u32,
+        /// The path of a configuration whose filters should be kept.
+        #[arg(long = "base", value_name = "PATH")]
+        base: Option<Box<Path>>,
+        /// The path to write the configuration to. If absent, it is printed instead.
+        #[arg(short = 'o', long = "output", value_name = "PATH")]
+        output: Option<Box<Path>>,
+    },
+    /// Create a color configuration by sampling each dye color from the blocks of a resource pack.
+    PaletteFromPack {
+        /// The path of the resource pack, either a zip archive or a directory.
+        pack: Box<Path>,
+        /// The block whose textures are sampled.
+        #[arg(long = "block", value_enum, default_value_t = PackBlock::Wool)]
+        block: PackBlock,
+        /// The path of a configuration whose filters should be kept.
+        #[arg(long = "base", value_name = "PATH")]
+        base: Option<Box<Path>>,
+        /// The path to write the configuration to. If absent, it is printed instead.
+        #[arg(short = 'o', long = "output", value_name = "PATH")]
+        output: Option<Box<Path>>,
+    },
+    /// Run every conversion described within a JSON or TOML job file.
+    Job {
+        /// The path of the job file.
+        path: Box<Path>,
+        /// Keep running the remaining entries after one fails, reporting every failure once the job is finished.
+        ///
+        /// Jobs are currently the only way to convert several files within one run, so this is not available for
+        /// single conversions.
+        ///
+        /// Mistakes within the job file itself, such as a missing or invalid configuration, still stop the job before
+        /// anything is converted.
+        #[arg(long = "continue-on-error")]
+        continue_on_error: bool,
+    },
+    /// Print each configured color as a hexadecimal RGB value, for use within web pages.
+    ColorMap {
+        /// The path of the color configuration.
+        config: Box<Path>,
+        /// The format of the color map.
+        #[arg(long = "format", value_enum, default_value_t = ColorMapFormat::Css)]
+        format: ColorMapFormat,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum PackBlock {
+    /// Dyed wool, such as `red_wool`.
+    Wool,
+    /// Dyed concrete, such as `red_concrete`.
+    Concrete,
+    /// Dyed terracotta, such as `red_terracotta`.
+    Terracotta,
+}
+
+impl PackBlock {
+    /// Returns the path of the given color's texture within a resource pack.
+    fn texture(self, color: DyeColor) -> String {
+        let block = match self {
+            Self::Wool => "wool",
+            Self::Concrete => "concrete",
+            Self::Terracotta => "terracotta",
+        };
+
+        format!("assets/minecraft/textures/block/{color}_{block}.png")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ColorMapFormat {
+    /// CSS custom properties, such as `--dye-light-blue: #3AB3DA;`.
+    Css,
+    /// A JSON object keyed by each color's name.
+    Json,
+}
+
+/// A list of conversions to run together.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Job {
+    /// The conversions to run, in order.
+    pub entries: Vec<JobEntry>,
+}
+
+/// A single conversion within a job, where every path is relative to the job file.
+#[derive(Clone, Debug, Deserialize)]
+pub struct JobEntry {
+    /// The path of the image to convert.
+    pub input: PathBuf,
+    /// The path of the color configuration to load.
+    pub config: PathBuf,
+    /// The directory to output the converted files into.
+    pub output: PathBuf,
+    /// The expected dye color. If absent, all colors will be generated.
+    #[serde(default)]
+    pub color: Option<DyeColor>,
+    /// Any additional command-line options, such as '--output-format zip'.
+    #[serde(default)]
+    pub options: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl FromStr for Region {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s.split(',').map(|v| v.trim().parse::<u32>()).collect::<Result<Vec<_>, _>>();
+        let values = values.map_err(|e| e.to_string())?;
+
+        match values[..] {
+            [_, _, 0, _] | [_, _, _, 0] => Err("the region must not be empty".to_string()),
+            [x, y, width, height] => Ok(Self { x, y, width, height }),
+            _ => Err("expected a region formatted as 'X,Y,WIDTH,HEIGHT'".to_string()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Size {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl FromStr for Size {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((width, height)) = s.split_once(['x', 'X']) else {
+            return Err("expected a size formatted as 'WIDTHxHEIGHT'".to_string());
+        };
+
+        let width = width.trim().parse().map_err(|e: std::num::ParseIntError| e.to_string())?;
+        let height = height.trim().parse().map_err(|e: std::num::ParseIntError| e.to_string())?;
+
+        Ok(Self { width, height })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ColorOverride {
+    pub color: DyeColor,
+    pub rgb: [u8; 3],
+}
+
+impl FromStr for ColorOverride {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((color, hex)) = s.split_once('=') else {
+            return Err("expected a color override formatted as 'COLOR=#RRGGBB'".to_string());
+        };
+
+        let color = <DyeColor as ValueEnum>::from_str(color.trim(), true)?;
+        let rgb = self::parse_hex(hex.trim())?;
+
+        Ok(Self { color, rgb })
+    }
+}
+
+fn parse_hex(s: &str) -> Result<[u8; 3], String> {
+    let digits = s.strip_prefix('#').unwrap_or(s);
+
+    if digits.len() != 6 || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(format!("expected a hex color formatted as '#RRGGBB', found '{s}'"));
+    }
+
+    let channel = |index: usize| u8::from_str_radix(&digits[index .. index + 2], 16).map_err(|e| e.to_string());
+
+    Ok([channel(0)?, channel(2)?, channel(4)?])
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CliError {
+    #[error("unable to find the configuration file at {0:?}")]
+    MissingConfig(Box<Path>),
+    #[error("unable to find the target file at {0:?}")]
+    MissingTarget(Box<Path>),
+    #[error("unable to find the job file at {0:?}")]
+    MissingJob(Box<Path>),
+    #[error("{0} of {1} job entries failed")]
+    FailedEntries(usize, usize),
+    #[error("the colors '{1}' and '{2}' would both be written using the name '{0}'")]
+    OutputCollision(String, DyeColor, DyeColor),
+    #[error("the hues {1} and {2} would both be written using the name '{0}'")]
+    HueCollision(String, f32, f32),
+}
+
+impl CliError {
+    /// Returns the machine-readable code of this error.
+    const fn code(&self) -> &'static str {
+        match self {
+            Self::MissingConfig(_) | Self::MissingTarget(_) => "missing_file",
+            Self::MissingJob(_) => "missing_job",
+            Self::FailedEntries(..) => "failed_entries",
+            Self::OutputCollision(..) | Self::HueCollision(..) => "output_collision",
+        }
+    }
+
+    /// Returns any values describing the cause of this error.
+    fn context(&self) -> Value {
+        match self {
+            Self::MissingConfig(path) | Self::MissingTarget(path) | Self::MissingJob(path) => json!({ "file": path }),
+            Self::FailedEntries(failed, total) => json!({ "failed": failed, "total": total }),
+            Self::OutputCollision(name, first, second) => json!({ "name": name, "colors": [first, second] }),
+            Self::HueCollision(name, first, second) => json!({ "name": name, "hues": [first, second] }),
+        }
+    }
 }
 
 #[macro_export]
@@ -91,18 +546,197 @@ macro_rules! assert_matches {
     };
 }
 
-fn main() -> Result<()> {
+fn main() -> ExitCode {
     let arguments = Arguments::parse();
 
-    assert!(arguments.config.try_exists()?, "unable to find the target file at {:?}", arguments.config);
-    assert!(arguments.path.try_exists()?, "unable to find the configuration file at {:?}", arguments.path);
+    match self::run(&arguments) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) if arguments.json_errors => {
+            eprintln!("{}", self::error_json(&error));
+
+            ExitCode::FAILURE
+        }
+        Err(error) => {
+            eprintln!("Error: {error:?}");
+
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn error_json(error: &anyhow::Error) -> Value {
+    let (code, context) = self::error_details(error);
+    let mut json = json!({ "code": code, "message": format!("{error:#}") });
+
+    if let Some(context) = context {
+        json["context"] = context;
+    }
+
+    json
+}
+
+/// Returns the machine-readable code of the given error, along with any values describing its cause.
+fn error_details(error: &anyhow::Error) -> (&'static str, Option<Value>) {
+    if let Some(error) = error.downcast_ref::<CliError>() {
+        return (error.code(), Some(error.context()));
+    }
+
+    if let Some(error) = error.downcast_ref::<amethyst_colorizer::Error>() {
+        return (self::library_error_code(error), self::library_error_context(error));
+    }
+
+    if error.is::<std::io::Error>() {
+        ("io", None)
+    } else if error.is::<serde_json::Error>() {
+        ("config", None)
+    } else {
+        ("other", None)
+    }
+}
+
+/// Returns the machine-readable code of the given library error.
+const fn library_error_code(error: &amethyst_colorizer::Error) -> &'static str {
+    use amethyst_colorizer::Error;
+
+    match error {
+        Error::InvalidFilter(..) => "invalid_filter",
+        Error::NonFiniteValue(..) => "non_finite_value",
+        Error::InvalidPasses(..) => "invalid_passes",
+        Error::AchromaticColor(..) => "achromatic_color",
+        Error::InvalidBlendFactor(..) => "invalid_blend_factor",
+        Error::InvalidMinSaturation(..) => "invalid_min_saturation",
+        Error::NonFiniteHueCorrection(..) => "non_finite_hue_correction",
+        Error::InvalidTarget(..) => "invalid_target",
+        Error::InvalidHsv(..) => "invalid_hsv",
+        Error::InvalidMapTolerance(..) => "invalid_map_tolerance",
+        Error::InvalidAlphaScale(..) => "invalid_alpha_scale",
+        Error::InvalidOutputName(..) => "invalid_output_name",
+        Error::UnknownPreset(..) => "unknown_preset",
+        Error::PresetCycle(..) => "preset_cycle",
+        Error::UnsupportedGrayOption(..) => "unsupported_gray_option",
+        Error::RecolorsGrayscale => "recolors_grayscale",
+        Error::EmptyImage(..) => "empty_image",
+        Error::MissingFilterFile(..) => "missing_file",
+        Error::FilterFileCycle(..) => "filter_file_cycle",
+        Error::MaskDimensions(..) => "mask_dimensions",
+        Error::DestinationDimensions(..) => "destination_dimensions",
+        Error::OriginalDimensions(..) => "original_dimensions",
+        Error::InvalidPaletteLayout(..) => "invalid_palette_layout",
+        Error::Image(..) => "image",
+        Error::Io(..) => "io",
+        Error::Json(..) | Error::Toml(..) | Error::Yaml(..) => "config",
+        Error::Base64(..) | Error::FragmentVersion(..) => "fragment",
+    }
+}
+
+/// Returns any values describing the cause of the given library error.
+fn library_error_context(error: &amethyst_colorizer::Error) -> Option<Value> {
+    use amethyst_colorizer::Error;
+
+    Some(match error {
+        Error::InvalidFilter(kind, target, operation) | Error::NonFiniteValue(kind, target, operation, _) => {
+            json!({ "type": kind, "target": target, "operation": operation })
+        }
+        Error::InvalidPasses(color, index, _) => json!({ "color": color, "filter_index": index }),
+        Error::AchromaticColor(color, rgb) => json!({ "color": color, "rgb": rgb }),
+        Error::InvalidBlendFactor(color, factor) => json!({ "color": color, "factor": factor }),
+        Error::InvalidMinSaturation(color, saturation) => json!({ "color": color, "saturation": saturation }),
+        Error::NonFiniteHueCorrection(color, correction) => json!({ "color": color, "correction": correction }),
+        Error::InvalidTarget(color) => json!({ "color": color }),
+        Error::InvalidHsv(color, hsv) => json!({ "color": color, "hsv": hsv }),
+        Error::InvalidMapTolerance(color, index, _) => json!({ "color": color, "mapping_index": index }),
+        Error::InvalidAlphaScale(color, scale) => json!({ "color": color, "scale": scale }),
+        Error::InvalidOutputName(color, name) => json!({ "color": color, "name": name }),
+        Error::UnknownPreset(name) | Error::PresetCycle(name) => json!({ "preset": name }),
+        Error::UnsupportedGrayOption(option) => json!({ "option": option }),
+        Error::EmptyImage(width, height) => json!({ "width": width, "height": height }),
+        Error::MissingFilterFile(path) | Error::FilterFileCycle(path) => json!({ "file": path }),
+        Error::MaskDimensions(mask, image) => json!({ "mask": mask, "image": image }),
+        Error::DestinationDimensions(destination, source) => json!({ "destination": destination, "source": source }),
+        Error::OriginalDimensions(original, image) => json!({ "original": original, "image": image }),
+        Error::InvalidPaletteLayout(columns, rows) => json!({ "columns": columns, "rows": rows }),
+        Error::Image(_)
+        | Error::Io(_)
+        | Error::Json(_)
+        | Error::Toml(_)
+        | Error::Yaml(_)
+        | Error::Base64(_)
+        | Error::FragmentVersion(_)
+        | Error::RecolorsGrayscale => return None,
+    })
+}
+
+fn run(arguments: &Arguments) -> Result<()> {
+    match arguments.command {
+        Some(Command::DiffConfig { ref old, ref new }) => return self::diff_config(old, new),
+        Some(Command::PaletteFromImage { ref image, columns, rows, ref base, ref output }) => {
+            return self::palette_from_image(image, columns, rows, base.as_deref(), output.as_deref());
+        }
+        Some(Command::PaletteFromPack { ref pack, block, ref base, ref output }) => {
+            return self::palette_from_pack(pack, block, base.as_deref(), output.as_deref());
+        }
+        Some(Command::Job { ref path, continue_on_error }) => return self::run_job(path, continue_on_error),
+        Some(Command::ColorMap { ref config, format }) => return self::color_map(config, format),
+        None => {}
+    }
+
+    if !arguments.config.try_exists()? {
+        bail!(CliError::MissingConfig(arguments.config.clone()));
+    }
+
+    if let Some(ref path) = arguments.path {
+        if !path.try_exists()? {
+            bail!(CliError::MissingTarget(path.clone()));
+        }
+    }
+
+    let file_extension = if arguments.use_default_base {
+        Some("png")
+    } else {
+        arguments.path.as_deref().and_then(Path::extension).and_then(|s| s.to_str())
+    };
+
+    let image_format = arguments.input_format.map_or_else(
+        || {
+            file_extension
+                .and_then(ImageFormat::from_extension)
+                .filter(|f| amethyst_colorizer::supported_input_formats().contains(f))
+        },
+        |format| Some(format.format()),
+    );
+
+    if let (Some(extension), None) = (file_extension, arguments.input_format) {
+        assert!(
+            extension == "zip" || image_format.is_some(),
+            "the specified file must be either a .zip or image file"
+        );
+    }
+
+    assert!(arguments.image_format != ImageEncoding::Awebp, "{AWEBP_UNSUPPORTED}");
 
-    let mut file_extension = None;
+    let mut config = Config::load(&arguments.config)?;
 
-    if let Some(extension) = arguments.path.extension().and_then(|s| s.to_str()) {
-        assert_matches!(extension, "png" | "zip", "the specified file must be either a .zip or .png file");
+    self::apply_overrides(arguments, &mut config)?;
 
-        file_extension = Some(extension);
+    config.validate()?;
+
+    if let Some(gamma) = arguments.gamma {
+        assert!(gamma.is_finite() && gamma > 0.0, "the gamma must be a positive number");
+        assert!(arguments.image_format != ImageEncoding::Tga, "a gamma may only be stored within png files");
+    }
+
+    if let Some(target) = arguments.target_luminance {
+        assert!((0.0 ..= 1.0).contains(&target), "the target luminance must be within 0.0 and 1.0");
+    }
+
+    if let Some(ref color) = arguments.color {
+        assert!(config.colors.contains_key(color), "the given color is missing from the configuration file");
+    }
+
+    if arguments.print_effective_hue {
+        self::print_effective_hue(&config);
+
+        return Ok(());
     }
 
     if arguments.output.try_exists()? {
@@ -111,47 +745,1247 @@ fn main() -> Result<()> {
         std::fs::create_dir_all(&arguments.output)?;
     }
 
-    let config: Config = serde_json::from_slice(&std::fs::read(&arguments.config)?)?;
+    let start = Instant::now();
+    let summary = match (file_extension, image_format) {
+        (_, Some(_)) if arguments.seed_palette.is_some() => self::main_seed_palette(arguments, &config)?,
+        (_, Some(_)) => self::main_png(arguments, &config)?,
+        (Some("zip") | None, None) => self::main_zip(arguments, &config)?,
+        (Some(extension), None) => bail!("unknown extension '{extension}'"),
+    };
 
-    if let Some(ref color) = arguments.color {
-        assert!(config.colors.contains_key(color), "the given color is missing from the configuration file");
+    for warning in &summary.warnings {
+        eprintln!("note: {warning}");
     }
 
-    match file_extension {
-        Some("png") => self::main_png(&arguments, &config),
-        Some("zip") | None => self::main_zip(arguments, config),
-        Some(extension) => bail!("unknown extension '{extension}'"),
+    for color in &summary.skipped {
+        eprintln!("note: skipped color '{color}' as its output is unchanged");
+    }
+
+    if arguments.stats {
+        self::print_stats(&summary, start.elapsed());
     }
-}
 
-fn main_png(arguments: &Arguments, config: &Config) -> Result<()> {
-    let image = image::open(&arguments.path)?;
+    if let Some(ref path) = arguments.summary_json {
+        self::write_summary_json(path, arguments, &summary, start.elapsed())?;
+    }
 
-    if let Some(ref color) = arguments.color {
-        let output = arguments.output.join(format!("{color}_amethyst.png"));
-        let mut buffer = image.to_rgba8();
+    if arguments.open && std::io::stdout().is_terminal() && std::env::var_os("CI").is_none() {
+        self::open_output(arguments, &summary);
+    }
+
+    Ok(())
+}
+
+/// Applies every command-line option that overrides a setting within the configuration.
+fn apply_overrides(arguments: &Arguments, config: &mut Config) -> Result<()> {
+    if let Some(seed) = arguments.seed {
+        for filter in config.colors.values_mut().flat_map(|c| c.filters.iter_mut()) {
+            filter.seed = seed;
+        }
+    }
 
-        let Some(config) = config.colors.get(color) else {
-            bail!("the given color is missing from the configuration file");
+    for ColorOverride { color, rgb } in &arguments.set_colors {
+        let Some(config) = config.colors.get_mut(color) else {
+            bail!("the color '{color}' is missing from the configuration file");
         };
 
-        amethyst_colorizer::transform_image(config, &mut buffer)?;
-        image::save_buffer(output, &buffer, buffer.width(), buffer.height(), image.color())?;
+        config.rgb = *rgb;
+        config.hsv = None;
+    }
 
-        return Ok(());
+    if let Some(metric) = arguments.palette_distance {
+        for color in config.colors.values_mut() {
+            color.distance_metric = metric;
+        }
+    }
+
+    if let Some(factor) = arguments.force_rgb {
+        for color in config.colors.values_mut() {
+            color.rgb_blend = Some(factor);
+        }
     }
 
-    for (color, config) in &config.colors {
-        let output = arguments.output.join(format!("{color}_amethyst.png"));
-        let mut buffer = image.to_rgba8();
+    Ok(())
+}
 
-        amethyst_colorizer::transform_image(config, &mut buffer)?;
-        image::save_buffer(output, &buffer, buffer.width(), buffer.height(), image.color())?;
+fn open_output(arguments: &Arguments, summary: &Summary) {
+    let sheet = summary.written.iter().find(|p| p.file_stem().is_some_and(|s| s == "amethyst_sheet"));
+    let path = match (summary.colors.len(), summary.written.first()) {
+        (1, Some(path)) => path.as_path(),
+        _ => sheet.map_or(&*arguments.output, PathBuf::as_path),
+    };
+
+    // Failing to open a viewer does not affect the output, so it is only reported.
+    if let Err(error) = opener::open(path) {
+        eprintln!("note: unable to open {}: {error}", path.display());
+    }
+}
+
+fn write_summary_json(path: &Path, arguments: &Arguments, summary: &Summary, elapsed: Duration) -> Result<()> {
+    let timestamp = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.as_secs();
+    let input = match arguments.path {
+        Some(ref path) if !arguments.use_default_base => path.to_string_lossy().into_owned(),
+        _ => "<default>".to_string(),
+    };
+
+    let outputs = summary.outputs.iter().map(|output| {
+        let [red, green, blue] = output.dominant_color;
+
+        json!({
+            "entry": output.entry,
+            "color": output.color,
+            "width": output.dimensions.0,
+            "height": output.dimensions.1,
+            "dominant_color": format!("#{red:02X}{green:02X}{blue:02X}"),
+        })
+    });
+
+    let document = json!({
+        "version": SUMMARY_VERSION,
+        "tool_version": env!("CARGO_PKG_VERSION"),
+        "timestamp": timestamp,
+        "config": {
+            "path": arguments.config,
+            "hash": format!("{:016x}", self::fnv1a(&std::fs::read(&arguments.config)?)),
+        },
+        "inputs": [input],
+        "outputs": outputs.collect::<Vec<_>>(),
+        "written": summary.written,
+        "skipped": summary.skipped,
+        "warnings": summary.warnings,
+        "elapsed_seconds": elapsed.as_secs_f64(),
+    });
+
+    self::write_atomic(path, |path| Ok(std::fs::write(path, serde_json::to_string_pretty(&document)?)?))
+}
+
+/// Returns the 64-bit FNV-1a hash of the given bytes, which is stable across platforms and releases.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(0xCBF2_9CE4_8422_2325, |hash, byte| (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01B3))
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn print_stats(summary: &Summary, elapsed: Duration) {
+    let images = summary.colors.len();
+    let seconds = elapsed.as_secs_f64();
+
+    eprintln!("images: {images}");
+    eprintln!("pixels: {}", summary.pixels);
+    eprintln!("elapsed: {seconds:.3}s");
+
+    if seconds > 0.0 {
+        eprintln!("images/sec: {:.2}", images as f64 / seconds);
+        eprintln!("pixels/sec: {:.0}", summary.pixels as f64 / seconds);
+    }
+}
+
+fn print_effective_hue(config: &Config) {
+    println!("{:<12} {:<7}  {:>7} {:>10} {:>5}", "color", "rgb", "hue", "saturation", "value");
+
+    for (color, config) in config.sorted_colors() {
+        let [red, green, blue] = config.rgb;
+        let hsv = config.target_hsv();
+        let hue = hsv.hue.into_positive_degrees();
+
+        println!(
+            "{:<12} #{red:02X}{green:02X}{blue:02X}  {hue:>7.2} {:>10.3} {:>5.3}",
+            color.to_string(),
+            hsv.saturation,
+            hsv.value
+        );
+    }
+}
+
+fn diff_config(old: &Path, new: &Path) -> Result<()> {
+    for path in [old, new] {
+        if !path.try_exists()? {
+            bail!(CliError::MissingConfig(path.into()));
+        }
+    }
+
+    let old = Config::from_path(old)?;
+    let new = Config::from_path(new)?;
+    let colors = old.colors.keys().chain(new.colors.keys()).copied().collect::<BTreeSet<_>>();
+    let mut changed = false;
+
+    for color in colors {
+        match (old.colors.get(&color), new.colors.get(&color)) {
+            (Some(_), None) => println!("- {color}"),
+            (None, Some(_)) => println!("+ {color}"),
+            (Some(old), Some(new)) if old != new => {
+                println!("~ {color}");
+
+                self::diff_color(old, new);
+            }
+            _ => continue,
+        }
+
+        changed = true;
+    }
+
+    if !changed {
+        println!("no differences");
     }
 
     Ok(())
 }
 
-fn main_zip(arguments: Arguments, config: Config) -> Result<()> {
+fn run_job(path: &Path, continue_on_error: bool) -> Result<()> {
+    if !path.try_exists()? {
+        bail!(CliError::MissingJob(path.into()));
+    }
+
+    let text = std::fs::read_to_string(path)?;
+    let job: Job = match path.extension().and_then(|s| s.to_str()) {
+        Some("toml") => toml::from_str(&text)?,
+        _ => serde_json::from_str(&text)?,
+    };
+
+    let base = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut runs = Vec::with_capacity(job.entries.len());
+
+    // Every entry is checked before anything is converted, so that a mistake never leaves a job half-finished.
+    for entry in job.entries {
+        let (input, config) = (base.join(entry.input), base.join(entry.config));
+
+        if !input.try_exists()? {
+            bail!(CliError::MissingTarget(input.into()));
+        }
+
+        if !config.try_exists()? {
+            bail!(CliError::MissingConfig(config.into()));
+        }
+
+        Config::from_path(&config)?;
+
+        let mut arguments: Vec<OsString> = vec!["amethyst-colorizer".into(), input.into()];
+
+        arguments.extend(["--config".into(), config.into(), "--output-dir".into(), base.join(entry.output).into()]);
+
+        if let Some(color) = entry.color.and_then(|c| c.to_possible_value()) {
+            arguments.extend(["--target-color".into(), color.get_name().into()]);
+        }
+
+        arguments.extend(entry.options.into_iter().map(OsString::from));
+
+        runs.push(Arguments::try_parse_from(arguments)?);
+    }
+
+    let mut failed = 0;
+
+    for (index, arguments) in runs.iter().enumerate() {
+        let input = arguments.path.as_deref().map_or_else(|| "<default>".into(), |p| p.display().to_string());
+
+        eprintln!("[{}/{}] {input}", index + 1, runs.len());
+
+        match self::run(arguments) {
+            Ok(()) => {}
+            Err(error) if continue_on_error => {
+                eprintln!("failed to convert {input}: {error:#}");
+
+                failed += 1;
+            }
+            Err(error) => return Err(error),
+        }
+    }
+
+    if failed > 0 {
+        bail!(CliError::FailedEntries(failed, runs.len()));
+    }
+
     Ok(())
 }
+
+fn palette_from_image(image: &Path, columns: u32, rows: u32, base: Option<&Path>, output: Option<&Path>) -> Result<()> {
+    if !image.try_exists()? {
+        bail!(CliError::MissingTarget(image.into()));
+    }
+
+    let palette = amethyst_colorizer::sample_palette(&image::open(image)?.to_rgba8(), columns, rows)?;
+
+    self::write_palette(palette, base, output)
+}
+
+fn palette_from_pack(pack: &Path, block: PackBlock, base: Option<&Path>, output: Option<&Path>) -> Result<()> {
+    if !pack.try_exists()? {
+        bail!(CliError::MissingTarget(pack.into()));
+    }
+
+    let mut archive = if pack.is_dir() { None } else { Some(ZipArchive::new(std::fs::File::open(pack)?)?) };
+    let mut palette = BTreeMap::new();
+
+    for color in DyeColor::ALL {
+        let texture = block.texture(color);
+        let bytes = if let Some(ref mut archive) = archive {
+            match archive.by_name(&texture) {
+                Ok(mut file) => {
+                    let mut bytes = Vec::new();
+
+                    file.read_to_end(&mut bytes)?;
+
+                    Some(bytes)
+                }
+                Err(ZipError::FileNotFound) => None,
+                Err(error) => return Err(error.into()),
+            }
+        } else {
+            let path = pack.join(&texture);
+
+            if path.try_exists()? { Some(std::fs::read(path)?) } else { None }
+        };
+
+        let Some(bytes) = bytes else {
+            eprintln!("note: skipped color '{color}' as '{texture}' is missing from the resource pack");
+
+            continue;
+        };
+
+        palette.insert(color, amethyst_colorizer::dominant_color(&image::load_from_memory(&bytes)?.to_rgba8()));
+    }
+
+    self::write_palette(palette, base, output)
+}
+
+/// Writes a configuration with the given colors, keeping every other setting from the base configuration if given.
+fn write_palette(palette: BTreeMap<DyeColor, [u8; 3]>, base: Option<&Path>, output: Option<&Path>) -> Result<()> {
+    let mut config = match base {
+        Some(path) if !path.try_exists()? => bail!(CliError::MissingConfig(path.into())),
+        Some(path) => Config::load(path)?,
+        None => Config { colors: BTreeMap::new(), output_depth: None, presets: BTreeMap::new() },
+    };
+
+    for (color, rgb) in palette {
+        let entry = config.colors.entry(color).or_insert_with(|| DyeColorConfig::new(rgb));
+
+        entry.rgb = rgb;
+        entry.hsv = None;
+    }
+
+    let json = config.to_pretty_json()?;
+
+    if let Some(path) = output {
+        self::write_atomic(path, |path| Ok(std::fs::write(path, json)?))
+    } else {
+        println!("{json}");
+
+        Ok(())
+    }
+}
+
+fn color_map(path: &Path, format: ColorMapFormat) -> Result<()> {
+    if !path.try_exists()? {
+        bail!(CliError::MissingConfig(path.into()));
+    }
+
+    let config = Config::from_path(path)?;
+    let colors = config.sorted_colors().into_iter().map(|(color, config)| {
+        let [red, green, blue] = config.rgb;
+
+        (color, format!("#{red:02X}{green:02X}{blue:02X}"))
+    });
+
+    match format {
+        ColorMapFormat::Css => {
+            println!(":root {{");
+
+            for (color, hex) in colors {
+                println!("    --dye-{}: {hex};", color.to_string().replace('_', "-"));
+            }
+
+            println!("}}");
+        }
+        ColorMapFormat::Json => {
+            let map = colors.map(|(color, hex)| (color.to_string(), Value::from(hex)));
+            let map = map.collect::<serde_json::Map<_, _>>();
+
+            println!("{}", serde_json::to_string_pretty(&map)?);
+        }
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::float_cmp)]
+fn diff_color(old: &DyeColorConfig, new: &DyeColorConfig) {
+    if old.rgb != new.rgb {
+        println!("    rgb: {:?} -> {:?}", old.rgb, new.rgb);
+    }
+
+    if old.hsv != new.hsv {
+        println!("    hsv: {:?} -> {:?}", old.hsv, new.hsv);
+    }
+
+    if old.allow_alpha != new.allow_alpha {
+        println!("    allow_alpha: {} -> {}", old.allow_alpha, new.allow_alpha);
+    }
+
+    if old.gamut_mapping != new.gamut_mapping {
+        println!("    gamut_mapping: {:?} -> {:?}", old.gamut_mapping, new.gamut_mapping);
+    }
+
+    if old.rounding != new.rounding {
+        println!("    rounding: {:?} -> {:?}", old.rounding, new.rounding);
+    }
+
+    if old.chroma_key != new.chroma_key || old.chroma_key_tolerance != new.chroma_key_tolerance {
+        println!(
+            "    chroma_key: {:?} ±{} -> {:?} ±{}",
+            old.chroma_key, old.chroma_key_tolerance, new.chroma_key, new.chroma_key_tolerance
+        );
+    }
+
+    if old.lock != new.lock {
+        println!("    lock: {:?} -> {:?}", old.lock, new.lock);
+    }
+
+    if old.rgb_blend != new.rgb_blend {
+        println!("    rgb_blend: {:?} -> {:?}", old.rgb_blend, new.rgb_blend);
+    }
+
+    if old.filters_enabled != new.filters_enabled {
+        println!("    filters_enabled: {} -> {}", old.filters_enabled, new.filters_enabled);
+    }
+
+    if old.min_saturation != new.min_saturation {
+        println!("    min_saturation: {} -> {}", old.min_saturation, new.min_saturation);
+    }
+
+    if old.output_name != new.output_name {
+        println!("    output_name: {:?} -> {:?}", old.output_name, new.output_name);
+    }
+
+    if old.hue_correction != new.hue_correction {
+        println!("    hue_correction: {} -> {}", old.hue_correction, new.hue_correction);
+    }
+
+    if old.alpha_scale != new.alpha_scale {
+        println!("    alpha_scale: {} -> {}", old.alpha_scale, new.alpha_scale);
+    }
+
+    if old.distance_metric != new.distance_metric {
+        println!("    distance_metric: {:?} -> {:?}", old.distance_metric, new.distance_metric);
+    }
+
+    if old.color_map != new.color_map {
+        println!("    color_map: {:?} -> {:?}", old.color_map, new.color_map);
+    }
+
+    if old.dither != new.dither {
+        println!("    dither: {} -> {}", old.dither, new.dither);
+    }
+
+    if old.filter_stage != new.filter_stage {
+        println!("    filter_stage: {:?} -> {:?}", old.filter_stage, new.filter_stage);
+    }
+
+    if old.opaque_bounds_only != new.opaque_bounds_only {
+        println!("    opaque_bounds_only: {} -> {}", old.opaque_bounds_only, new.opaque_bounds_only);
+    }
+
+    if old.preserve_transparent_rgb != new.preserve_transparent_rgb {
+        println!("    preserve_transparent_rgb: {} -> {}", old.preserve_transparent_rgb, new.preserve_transparent_rgb);
+    }
+
+    for index in 0 .. old.filters.len().max(new.filters.len()) {
+        match (old.filters.get(index), new.filters.get(index)) {
+            (Some(filter), None) => println!("    - filter #{index}: {filter:?}"),
+            (None, Some(filter)) => println!("    + filter #{index}: {filter:?}"),
+            (Some(old), Some(new)) if old != new => println!("    ~ filter #{index}: {old:?} -> {new:?}"),
+            _ => {}
+        }
+    }
+}
+
+/// Describes the results of a single run.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Summary {
+    /// The paths of every file that was written.
+    pub written: Vec<PathBuf>,
+    /// Every converted image that was produced.
+    pub outputs: Vec<Output>,
+    /// Every color that was processed.
+    pub colors: Vec<DyeColor>,
+    /// The total number of pixels within every processed image.
+    pub pixels: u64,
+    /// Every color that was skipped.
+    pub skipped: Vec<DyeColor>,
+    /// Any warnings that were raised.
+    pub warnings: Vec<String>,
+}
+
+/// Describes a single converted image.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Output {
+    /// The path of the image, relative to the output directory or archive.
+    pub entry: PathBuf,
+    /// The color that the image was converted into.
+    pub color: DyeColor,
+    /// The width and height of the image.
+    pub dimensions: (u32, u32),
+    /// The average color of every non-transparent pixel within the image.
+    pub dominant_color: [u8; 3],
+}
+
+/// The version of the format written by '--summary-json', which is incremented whenever it changes incompatibly.
+const SUMMARY_VERSION: u32 = 1;
+
+/// The number of columns within a contact sheet.
+const SHEET_COLUMNS: u32 = 4;
+
+/// The suffixes of companion textures that are copied alongside each output, used for normal, specular, and emissive
+/// maps.
+const COMPANION_SUFFIXES: [&str; 3] = ["n", "s", "e"];
+
+fn main_png(arguments: &Arguments, config: &Config) -> Result<Summary> {
+    let image = self::open_input(arguments)?;
+
+    self::check_size(arguments, image.width(), image.height())?;
+
+    let inputs = Inputs {
+        reference: arguments.histogram_reference.as_deref().map(image::open).transpose()?.map(|i| i.to_rgba8()),
+        mask: arguments.mask.as_deref().map(image::open).transpose()?.map(|i| i.to_luma8()),
+        assume_linear: arguments.assume_linear,
+        no_hue: arguments.no_hue,
+    };
+    let depth = config.output_depth.unwrap_or_else(|| {
+        let color = image.color();
+
+        if color.bytes_per_pixel() > color.channel_count() { BitDepth::Sixteen } else { BitDepth::Eight }
+    });
+
+    let colors = match arguments.color {
+        Some(color) => {
+            let Some(config) = config.colors.get(&color) else {
+                bail!("the given color is missing from the configuration file");
+            };
+
+            vec![(color, config)]
+        }
+        None => config.sorted_colors(),
+    };
+
+    // Every name is checked before anything is written, since a later output would otherwise silently replace an
+    // earlier one. Names are compared case-insensitively, as they refer to the same file on most file systems.
+    let mut names = BTreeMap::new();
+
+    for (color, config) in &colors {
+        let name = config.output_name(*color);
+
+        if let Some(previous) = names.insert(name.to_lowercase(), *color) {
+            bail!(CliError::OutputCollision(name, previous, *color));
+        }
+    }
+
+    let companions = self::read_companions(arguments)?;
+    let mut sink = Sink::new(arguments.output_format);
+    let mut summary = Summary::default();
+    let mut tiles = Vec::new();
+    let mut frames = Vec::new();
+
+    for (color, config) in colors {
+        if let Some(ref directory) = arguments.dump_stages {
+            self::dump_stages(directory, &image, config)?;
+        }
+
+        let variant = self::with_depth(&self::render(arguments, &mut summary, &inputs, &image, color, config)?, depth);
+
+        let unchanged = arguments.skip_unchanged
+            && variant.color() == image.color()
+            && variant.dimensions() == image.dimensions()
+            && variant.as_bytes() == image.as_bytes();
+
+        if unchanged {
+            summary.skipped.push(color);
+
+            continue;
+        }
+
+        if arguments.image_format == ImageEncoding::Apng {
+            let frame = variant.to_rgba8();
+
+            if arguments.summary_json.is_some() {
+                let entry = PathBuf::from(ANIMATION_ENTRY);
+                let dominant_color = amethyst_colorizer::dominant_color(&frame);
+
+                summary.outputs.push(Output { entry, color, dimensions: frame.dimensions(), dominant_color });
+            }
+
+            frames.push(frame);
+            summary.colors.push(color);
+            summary.pixels += u64::from(variant.width()) * u64::from(variant.height());
+        } else {
+            let name = config.output_name(color);
+
+            self::save(arguments, &mut sink, &mut summary, (color, &name), &variant)?;
+
+            for (suffix, extension, bytes) in &companions {
+                let entry = self::entry_path(arguments, &name, &format!("amethyst_{suffix}"), extension);
+
+                summary.written.extend(sink.write(arguments, &entry, bytes)?);
+            }
+        }
+
+        if arguments.sheet {
+            tiles.push((color, variant.to_rgba8()));
+        }
+    }
+
+    if arguments.sheet {
+        let sheet = DynamicImage::from(amethyst_colorizer::contact_sheet(&tiles, SHEET_COLUMNS, arguments.labels));
+        let entry = PathBuf::from(format!("amethyst_sheet.{}", arguments.image_format.extension()));
+
+        summary.written.extend(sink.write(arguments, &entry, &self::encode(&sheet, arguments)?)?);
+    }
+
+    if !frames.is_empty() {
+        let entry = PathBuf::from(ANIMATION_ENTRY);
+
+        summary
+            .written
+            .extend(sink.write(arguments, &entry, &self::encode_animation(&frames, arguments.gamma)?)?);
+    }
+
+    if arguments.include_source {
+        summary.written.extend(self::save_source(arguments, &mut sink)?);
+    }
+
+    summary.written.extend(sink.finish(arguments)?);
+
+    Ok(summary)
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn main_seed_palette(arguments: &Arguments, config: &Config) -> Result<Summary> {
+    let Some(scheme) = arguments.seed_palette else {
+        return Ok(Summary::default());
+    };
+
+    let image = self::open_input(arguments)?.to_rgba8();
+
+    self::check_size(arguments, image.width(), image.height())?;
+
+    // The filters of the target color are reused for every hue, if one is given.
+    let template = arguments.color.and_then(|c| config.colors.get(&c).cloned());
+    let hues = scheme.hues(arguments.palette_base_hue, arguments.palette_count);
+    let hues = hues
+        .into_iter()
+        .map(|hue| (format!("hue_{:03}", hue.round() as u32 % 360), hue))
+        .collect::<Vec<_>>();
+    let mut names = BTreeMap::new();
+
+    // Hues are named by their nearest degree, so nearby hues could otherwise silently replace each other's outputs.
+    for (name, hue) in &hues {
+        if let Some(previous) = names.insert(name, *hue) {
+            bail!(CliError::HueCollision(name.clone(), previous, *hue));
+        }
+    }
+
+    let mut sink = Sink::new(arguments.output_format);
+    let mut summary = Summary::default();
+
+    for (name, hue) in hues {
+        let hsv: Hsv = Hsv::new(hue, 1.0, 1.0);
+        let (red, green, blue) = Srgb::<f32>::from_color(hsv).into_format::<u8>().into_components();
+        let config = template.as_ref().map_or_else(
+            || DyeColorConfig::new([red, green, blue]),
+            |template| DyeColorConfig { rgb: [red, green, blue], hsv: None, ..template.clone() },
+        );
+
+        let mut variant = image.clone();
+
+        amethyst_colorizer::transform_image(&config, &mut variant)?;
+
+        let extension = arguments.image_format.extension();
+        let entry = PathBuf::from(format!("{name}_amethyst.{extension}"));
+        let bytes = self::encode(&variant.into(), arguments)?;
+
+        summary.written.extend(sink.write(arguments, &entry, &bytes)?);
+        summary.pixels += u64::from(image.width()) * u64::from(image.height());
+    }
+
+    summary.written.extend(sink.finish(arguments)?);
+
+    Ok(summary)
+}
+
+fn dump_stages(directory: &Path, image: &DynamicImage, config: &DyeColorConfig) -> Result<()> {
+    std::fs::create_dir_all(directory)?;
+
+    let mut index = 0_usize;
+
+    amethyst_colorizer::transform_image_staged(config, &mut image.to_rgba8(), |filter, stage| {
+        let name = match filter {
+            Some(filter) => serde_json::to_value(filter.target)?.as_str().unwrap_or("filter").to_string(),
+            None => "hue".to_string(),
+        };
+
+        stage.save_with_format(directory.join(format!("{index:02}_{name}.png")), ImageFormat::Png)?;
+
+        index += 1;
+
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+fn open_input(arguments: &Arguments) -> Result<DynamicImage> {
+    let path = match arguments.path {
+        Some(ref path) if !arguments.use_default_base => path,
+        _ => return Ok(amethyst_colorizer::default_base_image()?.into()),
+    };
+
+    let Some(format) = arguments.input_format else {
+        return Ok(image::open(path)?);
+    };
+
+    match image::load_from_memory_with_format(&std::fs::read(path)?, format.format()) {
+        Ok(image) => Ok(image),
+        Err(error) => bail!("unable to decode {path:?} as {format:?}: {error}"),
+    }
+}
+
+fn check_size(arguments: &Arguments, width: u32, height: u32) -> Result<()> {
+    let Some(Size { width: expected_width, height: expected_height }) = arguments.expect_size else {
+        return Ok(());
+    };
+
+    if (width, height) != (expected_width, expected_height) {
+        let path = arguments.path.as_deref().filter(|_| !arguments.use_default_base);
+        let path = path.map_or_else(|| "<default>".into(), |p| p.display().to_string());
+
+        bail!("expected {path} to be {expected_width}x{expected_height}, but it is {width}x{height}");
+    }
+
+    Ok(())
+}
+
+struct Inputs {
+    /// The histogram matching reference image.
+    reference: Option<RgbaImage>,
+    /// The mask limiting where images are converted.
+    mask: Option<GrayImage>,
+    /// Whether the input image is stored in linear light.
+    assume_linear: bool,
+    /// Whether only filters are applied, leaving the image's hue as-is.
+    no_hue: bool,
+}
+
+fn with_depth(image: &DynamicImage, depth: BitDepth) -> DynamicImage {
+    let color = image.color();
+
+    match (depth, color.has_color(), color.has_alpha()) {
+        (BitDepth::Eight, true, true) => image.to_rgba8().into(),
+        (BitDepth::Eight, true, false) => image.to_rgb8().into(),
+        (BitDepth::Eight, false, true) => image.to_luma_alpha8().into(),
+        (BitDepth::Eight, false, false) => image.to_luma8().into(),
+        (BitDepth::Sixteen, true, true) => image.to_rgba16().into(),
+        (BitDepth::Sixteen, true, false) => image.to_rgb16().into(),
+        (BitDepth::Sixteen, false, true) => image.to_luma_alpha16().into(),
+        (BitDepth::Sixteen, false, false) => image.to_luma16().into(),
+    }
+}
+
+fn render(
+    arguments: &Arguments,
+    summary: &mut Summary,
+    inputs: &Inputs,
+    image: &DynamicImage,
+    color: DyeColor,
+    config: &DyeColorConfig,
+) -> Result<DynamicImage> {
+    let mut variant = self::render_region(arguments, summary, inputs, image, color, config)?;
+
+    if let Some(ref mask) = inputs.mask {
+        let mut buffer = variant.to_rgba8();
+
+        amethyst_colorizer::blend_masked(&mut buffer, &image.to_rgba8(), mask)?;
+
+        variant = buffer.into();
+    }
+
+    if let Some(target) = arguments.target_luminance {
+        let mut buffer = variant.to_rgba8();
+
+        amethyst_colorizer::balance_luminance(&mut buffer, target);
+
+        variant = buffer.into();
+    }
+
+    if arguments.swatch_border == 0 {
+        return Ok(variant);
+    }
+
+    Ok(amethyst_colorizer::add_border(&variant.to_rgba8(), arguments.swatch_border, config.rgb).into())
+}
+
+fn render_region(
+    arguments: &Arguments,
+    summary: &mut Summary,
+    inputs: &Inputs,
+    image: &DynamicImage,
+    color: DyeColor,
+    config: &DyeColorConfig,
+) -> Result<DynamicImage> {
+    let Some(Region { x, y, width, height }) = arguments.region else {
+        return self::transform(summary, inputs, image, color, config);
+    };
+
+    let fits_x = x.checked_add(width).is_some_and(|w| w <= image.width());
+    let fits_y = y.checked_add(height).is_some_and(|h| h <= image.height());
+
+    assert!(
+        fits_x && fits_y,
+        "the region {x},{y},{width},{height} is outside of the {}x{} image",
+        image.width(),
+        image.height()
+    );
+
+    let variant = self::transform(summary, inputs, &image.crop_imm(x, y, width, height), color, config)?;
+    let mut output: DynamicImage = match variant.color() {
+        ColorType::L8 => image.to_luma8().into(),
+        ColorType::La8 => image.to_luma_alpha8().into(),
+        ColorType::Rgb8 => image.to_rgb8().into(),
+        _ => image.to_rgba8().into(),
+    };
+
+    image::imageops::replace(&mut output, &variant, i64::from(x), i64::from(y));
+
+    Ok(output)
+}
+
+fn transform(
+    summary: &mut Summary,
+    inputs: &Inputs,
+    image: &DynamicImage,
+    color: DyeColor,
+    config: &DyeColorConfig,
+) -> Result<DynamicImage> {
+    // Chroma keys, histogram matching, and filter-only runs are only supported for RGBA images.
+    let is_simple = !config.uses_chroma_key() && inputs.reference.is_none() && !inputs.no_hue;
+
+    if !image.color().has_color() {
+        if config.preserves_grayscale() && config.rgb_only_option().is_none() && is_simple {
+            let mut buffer = image.to_luma_alpha8();
+
+            amethyst_colorizer::transform_gray_image(config, &mut buffer)?;
+
+            let output = DynamicImage::ImageLumaA8(buffer);
+
+            return Ok(if image.color().has_alpha() { output } else { output.to_luma8().into() });
+        }
+
+        let warning = format!("converting the grayscale input to rgba to apply the filters of color '{color}'");
+
+        summary.warnings.push(warning);
+    } else if !image.color().has_alpha() && is_simple && !inputs.assume_linear {
+        let mut buffer = image.to_rgb8();
+
+        amethyst_colorizer::transform_rgb_image(config, &mut buffer)?;
+
+        return Ok(buffer.into());
+    }
+
+    let mut buffer = image.to_rgba8();
+
+    if inputs.no_hue {
+        amethyst_colorizer::apply_filters(config.active_filters(), &mut buffer)?;
+    } else if inputs.assume_linear {
+        amethyst_colorizer::transform_image_with_target(config, config.linear_target_hsv(), &mut buffer)?;
+    } else {
+        amethyst_colorizer::transform_image(config, &mut buffer)?;
+    }
+
+    if let Some(ref reference) = inputs.reference {
+        amethyst_colorizer::match_histogram(&mut buffer, reference);
+    }
+
+    Ok(buffer.into())
+}
+
+fn save(
+    arguments: &Arguments,
+    sink: &mut Sink,
+    summary: &mut Summary,
+    (color, name): (DyeColor, &str),
+    image: &DynamicImage,
+) -> Result<()> {
+    if arguments.report_colors {
+        let [red, green, blue] = amethyst_colorizer::dominant_color(&image.to_rgba8());
+
+        println!("{color}: #{red:02X}{green:02X}{blue:02X}");
+    }
+
+    let extension = arguments.image_format.extension();
+    let entry = self::entry_path(arguments, name, "amethyst", extension);
+
+    summary.written.extend(sink.write(arguments, &entry, &self::encode(image, arguments)?)?);
+
+    if arguments.summary_json.is_some() {
+        let dominant_color = amethyst_colorizer::dominant_color(&image.to_rgba8());
+        let dimensions = (image.width(), image.height());
+
+        summary.outputs.push(Output { entry, color, dimensions, dominant_color });
+    }
+
+    if arguments.emit_alpha_mask {
+        let mask = DynamicImage::from(amethyst_colorizer::alpha_mask(&image.to_rgba8()));
+        let entry = self::entry_path(arguments, name, "amethyst_alpha", extension);
+
+        summary.written.extend(sink.write(arguments, &entry, &self::encode(&mask, arguments)?)?);
+    }
+
+    if arguments.split_channels {
+        let rgba = image.to_rgba8();
+
+        for (index, channel) in ["r", "g", "b", "a"].into_iter().enumerate() {
+            let gray = GrayImage::from_fn(rgba.width(), rgba.height(), |x, y| Luma([rgba.get_pixel(x, y).0[index]]));
+            let gray = DynamicImage::from(gray);
+            let entry = self::entry_path(arguments, name, &format!("amethyst_{channel}"), extension);
+
+            summary.written.extend(sink.write(arguments, &entry, &self::encode(&gray, arguments)?)?);
+        }
+    }
+
+    if arguments.mipmaps {
+        for (level, mipmap) in (1_u32 ..).zip(amethyst_colorizer::mipmaps(&image.to_rgba8())) {
+            let mipmap = DynamicImage::from(mipmap);
+            let entry = self::entry_path(arguments, name, &format!("amethyst_mip{level}"), extension);
+
+            summary.written.extend(sink.write(arguments, &entry, &self::encode(&mipmap, arguments)?)?);
+        }
+    }
+
+    summary.colors.push(color);
+    summary.pixels += u64::from(image.width()) * u64::from(image.height());
+
+    Ok(())
+}
+
+/// Reads every companion texture stored next to the input file, such as `amethyst_n.png` for `amethyst.png`.
+///
+/// Companion textures describe properties like surface normals rather than color, so they are copied alongside each
+/// output as-is. Each is returned as its suffix, extension, and contents.
+fn read_companions(arguments: &Arguments) -> Result<Vec<(&'static str, String, Vec<u8>)>> {
+    let Some(ref path) = arguments.path else {
+        return Ok(Vec::new());
+    };
+
+    if arguments.use_default_base {
+        return Ok(Vec::new());
+    }
+
+    let (Some(stem), Some(extension)) = (path.file_stem(), path.extension()) else {
+        return Ok(Vec::new());
+    };
+    let (stem, extension) = (stem.to_string_lossy(), extension.to_string_lossy());
+    let mut companions = Vec::new();
+
+    for suffix in COMPANION_SUFFIXES {
+        let companion = path.with_file_name(format!("{stem}_{suffix}.{extension}"));
+
+        if companion.try_exists()? {
+            companions.push((suffix, extension.to_string(), std::fs::read(companion)?));
+        }
+    }
+
+    Ok(companions)
+}
+
+/// Writes the input file's original bytes under its canonical name, returning its path if written directly to the disk.
+fn save_source(arguments: &Arguments, sink: &mut Sink) -> Result<Option<PathBuf>> {
+    let (bytes, extension) = match arguments.path {
+        Some(ref path) if !arguments.use_default_base => {
+            let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("png").to_string();
+
+            (std::fs::read(path)?, extension)
+        }
+        _ => (amethyst_colorizer::DEFAULT_BASE_IMAGE.to_vec(), "png".to_string()),
+    };
+
+    sink.write(arguments, Path::new(&format!("amethyst.{extension}")), &bytes)
+}
+
+/// The destination that converted files are written into.
+enum Sink {
+    /// Files are written directly into the output directory.
+    Directory,
+    /// Files are collected into a zip archive.
+    Zip(ZipWriter<Cursor<Vec<u8>>>),
+    /// Files are collected into a gzipped tarball.
+    TarGz(tar::Builder<GzEncoder<Vec<u8>>>),
+}
+
+impl Sink {
+    fn new(format: OutputFormat) -> Self {
+        match format {
+            OutputFormat::Png => Self::Directory,
+            OutputFormat::Zip => Self::Zip(ZipWriter::new(Cursor::new(Vec::new()))),
+            OutputFormat::Targz => Self::TarGz(tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()))),
+        }
+    }
+
+    /// Writes the given file, returning its path if it was written directly to the disk.
+    fn write(&mut self, arguments: &Arguments, entry: &Path, bytes: &[u8]) -> Result<Option<PathBuf>> {
+        // Archive entries always use forward slashes, regardless of platform.
+        let name = entry.iter().map(|s| s.to_string_lossy()).collect::<Vec<_>>().join("/");
+
+        match self {
+            Self::Directory => {
+                let path = arguments.output.join(entry);
+
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+
+                self::write_atomic(&path, |path| Ok(std::fs::write(path, bytes)?))?;
+
+                Ok(Some(path))
+            }
+            Self::Zip(writer) => {
+                writer.start_file(name, FileOptions::default().compression_method(CompressionMethod::Deflated))?;
+                writer.write_all(bytes)?;
+
+                Ok(None)
+            }
+            Self::TarGz(builder) => {
+                let mut header = tar::Header::new_gnu();
+
+                header.set_size(u64::try_from(bytes.len())?);
+                header.set_mode(0o644);
+                header.set_cksum();
+
+                builder.append_data(&mut header, name, bytes)?;
+
+                Ok(None)
+            }
+        }
+    }
+
+    /// Finishes writing, returning the path of the archive if one was written.
+    fn finish(self, arguments: &Arguments) -> Result<Option<PathBuf>> {
+        let (name, bytes) = match self {
+            Self::Directory => return Ok(None),
+            Self::Zip(mut writer) => ("amethyst.zip", writer.finish()?.into_inner()),
+            Self::TarGz(builder) => ("amethyst.tar.gz", builder.into_inner()?.finish()?),
+        };
+
+        let path = arguments.output.join(name);
+
+        self::write_atomic(&path, |path| Ok(std::fs::write(path, bytes)?))?;
+
+        Ok(Some(path))
+    }
+}
+
+/// Writes a file by first writing into a temporary file beside it, then renaming it into place.
+///
+/// This ensures that the file at the given path is never left partially written, even if the process is killed.
+fn write_atomic(path: &Path, write: impl FnOnce(&Path) -> Result<()>) -> Result<()> {
+    let Some(name) = path.file_name().and_then(|s| s.to_str()) else {
+        bail!("invalid output path {path:?}");
+    };
+
+    let temporary = path.with_file_name(format!(".{name}.tmp"));
+
+    if let Err(error) = write(&temporary) {
+        // The temporary file may not exist if the write failed early, so this is allowed to fail.
+        let _ = std::fs::remove_file(&temporary);
+
+        return Err(error);
+    }
+
+    Ok(std::fs::rename(temporary, path)?)
+}
+
+fn encode(image: &DynamicImage, arguments: &Arguments) -> Result<Vec<u8>> {
+    let encoding = arguments.image_format;
+    let mut bytes = Cursor::new(Vec::new());
+
+    match (encoding, arguments.gamma) {
+        // TGA has no 16-bit encoding, so deeper images are reduced to 8 bits per channel first.
+        (ImageEncoding::Tga, _) => self::with_depth(image, BitDepth::Eight).write_to(&mut bytes, encoding.format())?,
+        (ImageEncoding::Png | ImageEncoding::Apng, Some(gamma)) => return self::encode_png(image, gamma),
+        (ImageEncoding::Png | ImageEncoding::Apng, None) => image.write_to(&mut bytes, encoding.format())?,
+        (ImageEncoding::Awebp, _) => bail!(AWEBP_UNSUPPORTED),
+    }
+
+    Ok(bytes.into_inner())
+}
+
+/// Encodes an image as a PNG file containing the given gamma.
+fn encode_png(image: &DynamicImage, gamma: f32) -> Result<Vec<u8>> {
+    let (color, depth) = match image.color() {
+        ColorType::L8 => (png::ColorType::Grayscale, png::BitDepth::Eight),
+        ColorType::La8 => (png::ColorType::GrayscaleAlpha, png::BitDepth::Eight),
+        ColorType::Rgb8 => (png::ColorType::Rgb, png::BitDepth::Eight),
+        ColorType::Rgba8 => (png::ColorType::Rgba, png::BitDepth::Eight),
+        ColorType::L16 => (png::ColorType::Grayscale, png::BitDepth::Sixteen),
+        ColorType::La16 => (png::ColorType::GrayscaleAlpha, png::BitDepth::Sixteen),
+        ColorType::Rgb16 => (png::ColorType::Rgb, png::BitDepth::Sixteen),
+        ColorType::Rgba16 => (png::ColorType::Rgba, png::BitDepth::Sixteen),
+        // PNG has no floating-point encoding, so any other image is stored with 16 bits per channel.
+        _ => return self::encode_png(&image.to_rgba16().into(), gamma),
+    };
+
+    // PNG files store 16-bit samples in big-endian order, while images store them in native order.
+    let data = image.as_flat_samples_u16().map_or_else(
+        || image.as_bytes().to_vec(),
+        |samples| samples.samples.iter().flat_map(|sample| sample.to_be_bytes()).collect(),
+    );
+
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, image.width(), image.height());
+
+    encoder.set_color(color);
+    encoder.set_depth(depth);
+    encoder.set_source_gamma(png::ScaledFloat::new(gamma));
+
+    let mut writer = encoder.write_header()?;
+
+    writer.write_image_data(&data)?;
+    writer.finish()?;
+
+    Ok(bytes)
+}
+
+/// The reason that '--format awebp' is rejected, as the image library is unable to encode animated WebP files.
+const AWEBP_UNSUPPORTED: &str = "animated webp files cannot be encoded yet, consider using '--format apng' instead";
+
+/// The path of the animation written by '--format apng', which contains a frame for every converted image.
+const ANIMATION_ENTRY: &str = "amethyst_animated.png";
+
+/// The time that each frame of an animation is shown for, in milliseconds.
+const FRAME_DELAY: u16 = 500;
+
+fn encode_animation(frames: &[RgbaImage], gamma: Option<f32>) -> Result<Vec<u8>> {
+    let Some(first) = frames.first() else {
+        bail!("an animation requires at least one frame");
+    };
+
+    assert!(
+        frames.iter().all(|f| f.dimensions() == first.dimensions()),
+        "every frame of an animation must have the same dimensions"
+    );
+
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, first.width(), first.height());
+
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(u32::try_from(frames.len())?, 0)?;
+    encoder.set_frame_delay(FRAME_DELAY, 1000)?;
+
+    if let Some(gamma) = gamma {
+        encoder.set_source_gamma(png::ScaledFloat::new(gamma));
+    }
+
+    let mut writer = encoder.write_header()?;
+
+    for frame in frames {
+        writer.write_image_data(frame.as_raw())?;
+    }
+
+    writer.finish()?;
+
+    Ok(bytes)
+}
+
+fn entry_path(arguments: &Arguments, name: &str, suffix: &str, extension: &str) -> PathBuf {
+    let file_name = format!("{name}_{suffix}.{extension}");
+
+    if arguments.group_by_color { Path::new(name).join(file_name) } else { PathBuf::from(file_name) }
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn main_zip(_arguments: &Arguments, _config: &Config) -> Result<Summary> {
+    Ok(Summary::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns an empty scratch directory with the given name.
+    fn scratch_dir(name: &str) -> Result<PathBuf> {
+        let directory = std::env::temp_dir().join("amethyst-colorizer-tests").join(name);
+
+        if directory.try_exists()? {
+            std::fs::remove_dir_all(&directory)?;
+        }
+
+        std::fs::create_dir_all(&directory)?;
+
+        Ok(directory)
+    }
+
+    #[test]
+    fn failed_atomic_writes_keep_the_destination() -> Result<()> {
+        let path = self::scratch_dir("atomic_writes")?.join("output.png");
+
+        std::fs::write(&path, "original")?;
+
+        let result = self::write_atomic(&path, |temporary| {
+            std::fs::write(temporary, "partial")?;
+
+            bail!("interrupted before the rename");
+        });
+
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_to_string(&path)?, "original");
+        assert!(!path.with_file_name(".output.png.tmp").try_exists()?);
+
+        self::write_atomic(&path, |temporary| Ok(std::fs::write(temporary, "replaced")?))?;
+
+        assert_eq!(std::fs::read_to_string(&path)?, "replaced");
+
+        Ok(())
+    }
+
+    #[test]
+    fn main_png_summarizes_every_output() -> Result<()> {
+        let output = self::scratch_dir("main_png")?;
+        let output_dir = output.to_string_lossy();
+        let config = Config::from_path(&Path::new(env!("CARGO_MANIFEST_DIR")).join("res/default.json"))?;
+        let arguments = Arguments::try_parse_from([
+            "amethyst-colorizer",
+            "--use-default-base",
+            "--summary-json",
+            "summary.json",
+            "-o",
+            &output_dir,
+        ])?;
+
+        let summary = self::main_png(&arguments, &config)?;
+
+        assert_eq!(summary.colors, config.colors.keys().copied().collect::<Vec<_>>());
+        assert_eq!(summary.pixels, 16 * 16 * 16);
+        assert!(summary.skipped.is_empty() && summary.warnings.is_empty());
+
+        for (output, color) in summary.outputs.iter().zip(&summary.colors) {
+            assert_eq!(output.entry, PathBuf::from(format!("{color}_amethyst.png")));
+            assert_eq!((output.color, output.dimensions), (*color, (16, 16)));
+        }
+
+        assert_eq!(summary.outputs.len(), 16);
+        assert_eq!(summary.written.len(), 16);
+        assert!(summary.written.iter().all(|path| path.is_file()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn palette_schemes_space_hues_evenly() -> Result<()> {
+        let even = PaletteScheme::Even.hues(0.0, 12);
+
+        assert_eq!(even.len(), 12);
+        assert!(even.iter().zip((0_u16 ..).map(f32::from)).all(|(hue, index)| (hue - 30.0 * index).abs() < 0.001));
+        assert_eq!(PaletteScheme::Even.hues(90.0, 0), [90.0]);
+        assert_eq!(PaletteScheme::Complementary.hues(90.0, 12), [90.0, 270.0]);
+        assert_eq!(PaletteScheme::Triadic.hues(300.0, 12), [300.0, 60.0, 180.0]);
+
+        Ok(())
+    }
+}
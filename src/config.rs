@@ -1,15 +1,383 @@
 use std::{
-    collections::HashMap,
-    fmt::{Display, Write},
+    collections::BTreeMap,
+    fmt::Display,
+    path::{Path, PathBuf},
 };
 
-use serde::{Deserialize, Serialize};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use palette::{FromColor, Hsv, LinSrgb, Srgb};
+use serde::de::{DeserializeOwned, Error as _};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+use crate::{Error, Result};
 
 /// The configuration file's format.
+///
+/// Any field that is equal to its default value is omitted when serialized, and every omitted field is filled with the
+/// same default value when deserialized. This means that deserializing a serialized valid configuration always produces
+/// an equal configuration, although the serialized form may be smaller than the original file. Preset references are
+/// always serialized in their expanded form.
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawConfig")]
 pub struct Config {
-    /// A list of dye colors and their set values.
-    pub colors: HashMap<DyeColor, DyeColorConfig>,
+    /// A list of dye colors and their set values, ordered by their declaration within [`DyeColor`].
+    pub colors: BTreeMap<DyeColor, DyeColorConfig>,
+    /// The bit depth of every output image. If absent, outputs match the depth of their input.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_depth: Option<BitDepth>,
+    /// Named filter chains that colors may reference within their filters, which are expanded when loaded.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub presets: BTreeMap<String, Box<[Filter]>>,
+}
+
+impl Config {
+    /// Reads, parses, and validates the configuration file at the given path.
+    ///
+    /// This is equivalent to calling [`Config::load`] followed by [`Config::validate`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file could not be loaded, or if the configuration is invalid.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let config = Self::load(path)?;
+
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Reads and parses the configuration file at the given path.
+    ///
+    /// The format is detected from the file's extension, where `.toml` files are parsed as TOML, `.yaml` and `.yml`
+    /// files are parsed as YAML, and any other file is parsed as JSON.
+    ///
+    /// A color's filters may be given as `{ "$ref": "path/to/filters.json" }`, in which case they are loaded from that
+    /// file. Paths are relative to the file containing the reference, and referenced files may themselves contain a
+    /// single reference.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file could not be read or parsed, or if a referenced filter file is
+    /// missing or references itself.
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut value = self::read_value(path)?;
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+
+        if let Some(colors) = value.get_mut("colors").and_then(Value::as_object_mut) {
+            for filters in colors.values_mut().filter_map(|color| color.get_mut("filters")) {
+                self::resolve_filter_file(filters, base, &mut Vec::new())?;
+            }
+        }
+
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Validates the configuration, ensuring that every color is safe to apply.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any color's configuration is invalid; see [`DyeColorConfig::validate`].
+    pub fn validate(&self) -> Result<()> {
+        for (color, config) in &self.colors {
+            config.validate(*color)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns every configured color, in the order that they are declared within [`DyeColor`].
+    #[must_use]
+    pub fn sorted_colors(&self) -> Vec<(DyeColor, &DyeColorConfig)> {
+        // The colors are already stored in order, but this keeps the order explicit for callers that depend on it.
+        let mut colors = self.colors.iter().map(|(color, config)| (*color, config)).collect::<Vec<_>>();
+
+        colors.sort_by_key(|(color, _)| *color);
+
+        colors
+    }
+
+    /// Serializes the configuration into an indented JSON string.
+    ///
+    /// Colors are always written in the order that they are declared within [`DyeColor`], so serializing the same
+    /// configuration will always produce the same output.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the configuration could not be serialized.
+    pub fn to_pretty_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Encodes the configuration into a compact, URL-safe string that may be shared as part of a link.
+    ///
+    /// The same configuration will always produce the same string. See [`FRAGMENT_VERSION`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the configuration could not be serialized.
+    pub fn to_fragment(&self) -> Result<String> {
+        self::encode_fragment(self)
+    }
+
+    /// Decodes a configuration from a string created by [`Config::to_fragment`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the string was created with an unsupported version or is malformed.
+    pub fn from_fragment(fragment: &str) -> Result<Self> {
+        self::decode_fragment(fragment)
+    }
+}
+
+/// Reads and parses the file at the given path, detecting its format from its extension.
+///
+/// # Errors
+///
+/// This function will return an error if the file could not be read or parsed.
+fn read_value(path: &Path) -> Result<Value> {
+    let text = std::fs::read_to_string(path)?;
+
+    Ok(match path.extension().and_then(|s| s.to_str()) {
+        Some("toml") => toml::from_str(&text)?,
+        Some("yaml" | "yml") => serde_yaml::from_str(&text)?,
+        _ => serde_json::from_str(&text)?,
+    })
+}
+
+/// Replaces the given filters with the contents of the file that they reference, if they are a reference.
+///
+/// # Errors
+///
+/// This function will return an error if the referenced file is missing, could not be parsed, or references itself.
+fn resolve_filter_file(filters: &mut Value, base: &Path, stack: &mut Vec<PathBuf>) -> Result<()> {
+    let Some(reference) = filters.get("$ref").and_then(Value::as_str) else {
+        return Ok(());
+    };
+
+    let path = base.join(reference);
+    let Ok(canonical) = std::fs::canonicalize(&path) else {
+        return Err(Error::MissingFilterFile(path));
+    };
+
+    if stack.contains(&canonical) {
+        return Err(Error::FilterFileCycle(path));
+    }
+
+    let mut loaded = self::read_value(&canonical)?;
+
+    stack.push(canonical);
+
+    self::resolve_filter_file(&mut loaded, path.parent().unwrap_or_else(|| Path::new("")), stack)?;
+
+    stack.pop();
+
+    *filters = loaded;
+
+    Ok(())
+}
+
+/// The version of the format used by shareable configuration fragments.
+///
+/// This is written at the start of every fragment and must be incremented whenever the configuration format changes
+/// in a way that older fragments would be decoded differently.
+pub const FRAGMENT_VERSION: u32 = 1;
+
+/// Encodes the given value as compact JSON within a versioned, URL-safe base64 string.
+///
+/// # Errors
+///
+/// This function will return an error if the value could not be serialized.
+fn encode_fragment<T: Serialize>(value: &T) -> Result<String> {
+    let json = serde_json::to_vec(value)?;
+
+    Ok(format!("{FRAGMENT_VERSION}.{}", URL_SAFE_NO_PAD.encode(json)))
+}
+
+/// Decodes a value from a string created by [`encode_fragment`].
+///
+/// # Errors
+///
+/// This function will return an error if the string has an unsupported version or is malformed.
+fn decode_fragment<T: DeserializeOwned>(fragment: &str) -> Result<T> {
+    let (version, data) = fragment.split_once('.').unwrap_or(("", fragment));
+
+    if version != FRAGMENT_VERSION.to_string() {
+        return Err(Error::FragmentVersion(version.to_string()));
+    }
+
+    Ok(serde_json::from_slice(&URL_SAFE_NO_PAD.decode(data)?)?)
+}
+
+/// The number of bits used to store each channel of an image.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "u8", into = "u8")]
+pub enum BitDepth {
+    /// Eight bits per channel.
+    Eight,
+    /// Sixteen bits per channel.
+    Sixteen,
+}
+
+impl TryFrom<u8> for BitDepth {
+    type Error = String;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            8 => Ok(Self::Eight),
+            16 => Ok(Self::Sixteen),
+            _ => Err(format!("invalid bit depth '{value}', expected either 8 or 16")),
+        }
+    }
+}
+
+impl From<BitDepth> for u8 {
+    fn from(value: BitDepth) -> Self {
+        match value {
+            BitDepth::Eight => 8,
+            BitDepth::Sixteen => 16,
+        }
+    }
+}
+
+/// The configuration file's format before any preset references have been expanded.
+#[derive(Deserialize)]
+struct RawConfig {
+    colors: BTreeMap<DyeColor, RawDyeColorConfig>,
+    #[serde(default)]
+    output_depth: Option<BitDepth>,
+    #[serde(default)]
+    presets: BTreeMap<String, Box<[FilterEntry]>>,
+}
+
+/// A single dye color's configuration before any preset references have been expanded.
+#[derive(Deserialize)]
+struct RawDyeColorConfig {
+    #[serde(default)]
+    filters: Box<[FilterEntry]>,
+    #[serde(default)]
+    rgb: Option<[u8; 3]>,
+    #[serde(flatten)]
+    config: DyeColorConfig,
+}
+
+/// Either a filter or a reference to a named filter preset.
+enum FilterEntry {
+    Preset { preset: String },
+    Filter(Filter),
+}
+
+impl<'de> Deserialize<'de> for FilterEntry {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+
+        // Anything that isn't a preset reference is parsed as a filter directly, so that its errors are kept as-is.
+        if let Some(preset) = value.get("preset") {
+            return Ok(Self::Preset { preset: String::deserialize(preset).map_err(D::Error::custom)? });
+        }
+
+        Filter::deserialize(value).map(Self::Filter).map_err(D::Error::custom)
+    }
+}
+
+impl TryFrom<RawConfig> for Config {
+    type Error = Error;
+
+    fn try_from(raw: RawConfig) -> Result<Self> {
+        let mut presets = BTreeMap::new();
+
+        for name in raw.presets.keys() {
+            self::resolve_preset(name, &raw.presets, &mut presets, &mut Vec::new())?;
+        }
+
+        let mut colors = BTreeMap::new();
+
+        for (color, RawDyeColorConfig { filters, rgb, mut config }) in raw.colors {
+            config.rgb = match (rgb, config.hsv) {
+                (Some(rgb), None) => rgb,
+                (None, Some(hsv)) => self::hsv_to_rgb(hsv),
+                // A saved configuration includes both values, so they are only rejected if they disagree.
+                (Some(rgb), Some(hsv)) if rgb == self::hsv_to_rgb(hsv) => rgb,
+                _ => return Err(Error::InvalidTarget(color)),
+            };
+            config.filters = self::expand_filters(&filters, &raw.presets, &mut presets, &mut Vec::new())?.into();
+
+            colors.insert(color, config);
+        }
+
+        Ok(Self { colors, output_depth: raw.output_depth, presets })
+    }
+}
+
+/// Converts the given hue, in degrees, saturation, and value into the nearest RGB components.
+fn hsv_to_rgb([hue, saturation, value]: [f32; 3]) -> [u8; 3] {
+    let hsv: Hsv = Hsv::new(hue, saturation, value);
+
+    Srgb::<f32>::from_color(hsv).into_format::<u8>().into_components().into()
+}
+
+/// Returns whether the given name can be used as a single file name without referring to any other directory.
+fn is_file_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && !name.contains("..") && !name.contains(['/', '\\'])
+}
+
+/// Expands every preset reference within the given filter entries.
+///
+/// # Errors
+///
+/// This function will return an error if a preset is unknown or references itself.
+fn expand_filters(
+    entries: &[FilterEntry],
+    raw: &BTreeMap<String, Box<[FilterEntry]>>,
+    resolved: &mut BTreeMap<String, Box<[Filter]>>,
+    stack: &mut Vec<String>,
+) -> Result<Vec<Filter>> {
+    let mut filters = Vec::with_capacity(entries.len());
+
+    for entry in entries {
+        match entry {
+            FilterEntry::Filter(filter) => filters.push(*filter),
+            FilterEntry::Preset { preset } => {
+                filters.extend_from_slice(&self::resolve_preset(preset, raw, resolved, stack)?);
+            }
+        }
+    }
+
+    Ok(filters)
+}
+
+/// Returns the expanded filters of the given preset, caching the result.
+///
+/// # Errors
+///
+/// This function will return an error if the preset is unknown or references itself.
+fn resolve_preset(
+    name: &str,
+    raw: &BTreeMap<String, Box<[FilterEntry]>>,
+    resolved: &mut BTreeMap<String, Box<[Filter]>>,
+    stack: &mut Vec<String>,
+) -> Result<Box<[Filter]>> {
+    if let Some(filters) = resolved.get(name) {
+        return Ok(filters.clone());
+    }
+
+    if stack.iter().any(|s| s == name) {
+        return Err(Error::PresetCycle(name.to_string()));
+    }
+
+    let Some(entries) = raw.get(name) else {
+        return Err(Error::UnknownPreset(name.to_string()));
+    };
+
+    stack.push(name.to_string());
+
+    let filters: Box<[Filter]> = self::expand_filters(entries, raw, resolved, stack)?.into();
+
+    stack.pop();
+    resolved.insert(name.to_string(), filters.clone());
+
+    Ok(filters)
 }
 
 /// All possible dye colors.
@@ -36,6 +404,28 @@ pub enum DyeColor {
     Pink,
 }
 
+impl DyeColor {
+    /// Every dye color, in order of declaration.
+    pub const ALL: [Self; 16] = [
+        Self::White,
+        Self::LightGray,
+        Self::Gray,
+        Self::Black,
+        Self::Brown,
+        Self::Red,
+        Self::Orange,
+        Self::Yellow,
+        Self::Lime,
+        Self::Green,
+        Self::Cyan,
+        Self::LightBlue,
+        Self::Blue,
+        Self::Purple,
+        Self::Magenta,
+        Self::Pink,
+    ];
+}
+
 impl Display for DyeColor {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let color = serde_json::to_string(self).unwrap_or_else(|_| "dyed".to_string());
@@ -45,19 +435,414 @@ impl Display for DyeColor {
 }
 
 /// Configuration for a single dye color.
+// Each flag is an independent option that is written directly within configuration files, so grouping them would
+// change the file format without making any combination of them invalid.
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct DyeColorConfig {
     /// The RBG components.
+    ///
+    /// Within a configuration file, this may be omitted if an HSV target is given instead, in which case it is set to
+    /// the nearest RGB value.
+    #[serde(default)]
     pub rgb: [u8; 3],
+    /// The hue, in degrees, saturation, and value that images are recolored towards, used in place of the RGB
+    /// components.
+    ///
+    /// If both are given within a configuration file, the RGB components must be the nearest RGB value to this.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hsv: Option<[f32; 3]>,
     /// Whether to allow alpha transparency.
     #[serde(default = "get_bool::<true>", skip_serializing_if = "check_bool::<true>")]
     pub allow_alpha: bool,
     /// The color's filters.
     #[serde(default, skip_serializing_if = "<[_]>::is_empty")]
     pub filters: Box<[Filter]>,
+    /// The strategy used to bring out-of-gamut colors back into sRGB.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub gamut_mapping: GamutMapping,
+    /// The rounding mode used when quantizing channels to 8 bits.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub rounding: Rounding,
+    /// Whether the error from quantizing channels to 8 bits is diffused into neighboring pixels, which reduces banding
+    /// within smooth gradients.
+    #[serde(default, skip_serializing_if = "check_bool::<false>")]
+    pub dither: bool,
+    /// A list of source colors that are each replaced with a target color, used in place of replacing the hue.
+    ///
+    /// If given, each pixel is replaced by the target of the nearest source color within its tolerance, and pixels
+    /// that match no source color are left as-is.
+    #[serde(default, skip_serializing_if = "<[_]>::is_empty")]
+    pub color_map: Box<[ColorMapping]>,
+    /// The metric used to measure the distance between a pixel and each source color of the color map, or the chroma
+    /// key.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub distance_metric: DistanceMetric,
+    /// A factor that every pixel's alpha is multiplied by once the image is transformed, where the result is clamped
+    /// within 0.0 and 1.0. Ignored if alpha is not allowed.
+    #[serde(default = "get_one", skip_serializing_if = "is_one")]
+    pub alpha_scale: f32,
+    /// A source color that is made fully transparent before recoloring. Ignored if alpha is not allowed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chroma_key: Option<[u8; 3]>,
+    /// The largest distance between a pixel and the chroma key for it to still match, measured using the color's
+    /// distance metric.
+    #[serde(default, skip_serializing_if = "check_u8::<0>")]
+    pub chroma_key_tolerance: u8,
+    /// The pixel components that are restored to their original values after every filter has been applied.
+    ///
+    /// Only the hue, saturation, and brightness targets may be locked; any other target is ignored.
+    #[serde(default, skip_serializing_if = "<[_]>::is_empty")]
+    pub lock: Box<[FilterTarget]>,
+    /// If present, each pixel is linearly blended towards the RGB components by this factor, from 0.0 to 1.0, rather
+    /// than having its hue replaced.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rgb_blend: Option<f32>,
+    /// Whether the color's filters are applied. If disabled, only the hue is replaced.
+    #[serde(default = "get_bool::<true>", skip_serializing_if = "check_bool::<true>")]
+    pub filters_enabled: bool,
+    /// The lowest saturation of each pixel after its hue is replaced, from 0.0 to 1.0. Raising this allows gray pixels
+    /// to take on the color.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub min_saturation: f32,
+    /// The name used for this color within output file names. If absent, the dye color's name is used.
+    ///
+    /// This may not be empty or contain path separators or `..`, so that outputs are always written within the output
+    /// directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_name: Option<String>,
+    /// Whether fully transparent pixels keep their original RGB components, which some tools rely on.
+    #[serde(default, skip_serializing_if = "check_bool::<false>")]
+    pub preserve_transparent_rgb: bool,
+    /// An offset, in degrees, added to the target hue after it is computed from the RGB components.
+    ///
+    /// Replacing the HSV hue of some colors, especially blues and purples, may look different to how the color appears
+    /// in-game, so this allows the hue to be nudged towards its perceived appearance without changing the RGB value.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub hue_correction: f32,
+    /// Whether image filters only apply within the bounding box of the image's non-transparent pixels.
+    ///
+    /// This avoids spending time on, and introducing artifacts within, the empty space of mostly-transparent images.
+    #[serde(default, skip_serializing_if = "check_bool::<false>")]
+    pub opaque_bounds_only: bool,
+    /// Whether the color's filters are applied before or after the image's hue is replaced.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub filter_stage: FilterStage,
 }
 
+impl DyeColorConfig {
+    /// Creates a new color configuration with the given RGB components and no filters.
+    #[must_use]
+    pub fn new(rgb: [u8; 3]) -> Self {
+        Self {
+            rgb,
+            allow_alpha: true,
+            filters: Box::default(),
+            gamut_mapping: GamutMapping::default(),
+            rounding: Rounding::default(),
+            chroma_key: None,
+            chroma_key_tolerance: 0,
+            lock: Box::default(),
+            rgb_blend: None,
+            filters_enabled: true,
+            min_saturation: 0.0,
+            output_name: None,
+            preserve_transparent_rgb: false,
+            hue_correction: 0.0,
+            opaque_bounds_only: false,
+            hsv: None,
+            filter_stage: FilterStage::AfterHue,
+            dither: false,
+            color_map: Box::default(),
+            distance_metric: DistanceMetric::default(),
+            alpha_scale: 1.0,
+        }
+    }
+
+    /// Validates the configuration of the given color.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the color's HSV target is out of range or its RGB value is not the nearest
+    /// to that target, if its RGB value is achromatic, if its RGB blend factor or minimum saturation is outside of 0.0
+    /// and 1.0, if any color mapping has a negative or non-finite tolerance, if its alpha scale is negative or
+    /// non-finite, if its output name is not a plain file name, if its hue correction is NaN or infinite, or if any
+    /// filter has a NaN or infinite value or an invalid number of passes.
+    pub fn validate(&self, color: DyeColor) -> Result<()> {
+        if let Some(hsv) = self.hsv.filter(|[hue, saturation, value]| {
+            !hue.is_finite() || !(0.0 ..= 1.0).contains(saturation) || !(0.0 ..= 1.0).contains(value)
+        }) {
+            return Err(Error::InvalidHsv(color, hsv));
+        }
+
+        // Configurations built in code skip the conversion done when loading, so the RGB value may have been left out.
+        if self.hsv.is_some_and(|hsv| self.rgb != self::hsv_to_rgb(hsv)) {
+            return Err(Error::InvalidTarget(color));
+        }
+
+        let [red, green, blue] = self.rgb;
+
+        // A gray value has an undefined hue, so replacing an image's hue with it would silently do nothing.
+        // Blending in RGB space and color maps do not rely on the hue, so any value is allowed there.
+        if red == green && green == blue && self.rgb_blend.is_none() && self.color_map.is_empty() {
+            return Err(Error::AchromaticColor(color, self.rgb));
+        }
+
+        if let Some(factor) = self.rgb_blend.filter(|f| !(0.0 ..= 1.0).contains(f)) {
+            return Err(Error::InvalidBlendFactor(color, factor));
+        }
+
+        if !(0.0 ..= 1.0).contains(&self.min_saturation) {
+            return Err(Error::InvalidMinSaturation(color, self.min_saturation));
+        }
+
+        for (index, mapping) in self.color_map.iter().enumerate() {
+            if !mapping.tolerance.is_finite() || mapping.tolerance < 0.0 {
+                return Err(Error::InvalidMapTolerance(color, index, mapping.tolerance));
+            }
+        }
+
+        if !self.alpha_scale.is_finite() || self.alpha_scale < 0.0 {
+            return Err(Error::InvalidAlphaScale(color, self.alpha_scale));
+        }
+
+        if let Some(name) = self.output_name.as_ref().filter(|n| !self::is_file_name(n)) {
+            return Err(Error::InvalidOutputName(color, name.clone()));
+        }
+
+        // A finite correction may still overflow once added to a very large hue.
+        if !self.hue_correction.is_finite() || !self.target_hsv().hue.into_inner().is_finite() {
+            return Err(Error::NonFiniteHueCorrection(color, self.hue_correction));
+        }
+
+        for (index, filter) in self.filters.iter().enumerate() {
+            let values = [filter.value, filter.source_hue, filter.tolerance];
+
+            if let Some(value) = values.into_iter().find(|v| !v.is_finite()) {
+                return Err(Error::NonFiniteValue(filter.kind, filter.target, filter.operation, value));
+            }
+
+            if !(1 ..= MAX_FILTER_PASSES).contains(&filter.passes) {
+                return Err(Error::InvalidPasses(color, index, filter.passes));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Encodes the color configuration into a compact, URL-safe string that may be shared as part of a link.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the configuration could not be serialized.
+    pub fn to_fragment(&self) -> Result<String> {
+        self::encode_fragment(self)
+    }
+
+    /// Decodes a color configuration from a string created by [`DyeColorConfig::to_fragment`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the string was created with an unsupported version or is malformed.
+    pub fn from_fragment(fragment: &str) -> Result<Self> {
+        self::decode_fragment(fragment)
+    }
+
+    /// Returns the name used for the given color within output file names.
+    #[must_use]
+    pub fn output_name(&self, color: DyeColor) -> String {
+        self.output_name.clone().unwrap_or_else(|| color.to_string())
+    }
+
+    /// Returns the filters that are applied by this configuration, which are empty if filters are disabled.
+    #[must_use]
+    pub fn active_filters(&self) -> &[Filter] {
+        if self.filters_enabled { &self.filters } else { &[] }
+    }
+
+    /// Returns the color that images are recolored towards, as HSV.
+    ///
+    /// This includes the configured hue correction.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use amethyst_colorizer::config::DyeColorConfig;
+    ///
+    /// let mut config = DyeColorConfig::new([0, 0, 255]);
+    /// let hsv = config.target_hsv();
+    ///
+    /// assert_eq!(hsv.hue.into_positive_degrees(), 240.0);
+    /// assert_eq!((hsv.saturation, hsv.value), (1.0, 1.0));
+    ///
+    /// config.hue_correction = 30.0;
+    ///
+    /// assert_eq!(config.target_hsv().hue.into_positive_degrees(), 270.0);
+    /// ```
+    #[must_use]
+    pub fn target_hsv(&self) -> Hsv<palette::encoding::Srgb> {
+        let mut hsv: Hsv<palette::encoding::Srgb> = match self.hsv {
+            Some([hue, saturation, value]) => Hsv::new(hue, saturation, value),
+            None => Hsv::from_color(Srgb::from_components(self.rgb.into()).into_format::<f32>()),
+        };
+
+        hsv.hue += self.hue_correction;
+
+        hsv
+    }
+
+    /// Returns the color that images stored in linear light are recolored towards, as HSV.
+    ///
+    /// Pixels are never passed through a transfer function when transformed, so an image stored in linear light must
+    /// be given a target computed from linearized RGB components to be recolored towards the same hue.
+    #[must_use]
+    pub fn linear_target_hsv(&self) -> Hsv<palette::encoding::Srgb> {
+        let srgb: Srgb = match self.hsv {
+            Some([hue, saturation, value]) => {
+                let hsv: Hsv = Hsv::new(hue, saturation, value);
+
+                Srgb::from_color(hsv)
+            }
+            None => Srgb::from_components(self.rgb.into()).into_format(),
+        };
+        let linear: LinSrgb = srgb.into_linear();
+        let mut hsv: Hsv<palette::encoding::Srgb> = Hsv::from_color(Srgb::from_components(linear.into_components()));
+
+        hsv.hue += self.hue_correction;
+
+        hsv
+    }
+
+    /// Returns whether this configuration makes pixels matching its chroma key transparent.
+    #[must_use]
+    pub const fn uses_chroma_key(&self) -> bool {
+        self.allow_alpha && self.chroma_key.is_some()
+    }
+
+    /// Returns whether this configuration can be applied to a grayscale image without introducing any color.
+    ///
+    /// This is only true if the color does not blend in RGB space, has no minimum saturation or color map, and every
+    /// filter exclusively targets brightness or contrast.
+    #[must_use]
+    pub fn preserves_grayscale(&self) -> bool {
+        let is_gray = |f: &Filter| matches!(f.target, FilterTarget::Brightness | FilterTarget::Contrast);
+        let is_recolored = self.rgb_blend.is_some() || self.min_saturation > 0.0 || !self.color_map.is_empty();
+
+        !is_recolored && self.active_filters().iter().all(is_gray)
+    }
+
+    /// Returns the name of the first option used by this configuration that is only supported for RGB images, if any.
+    ///
+    /// Locked components, preserved transparent pixels, and opaque bounds all rely on the RGB components of the
+    /// original image, while dithering is only performed across RGB channels.
+    #[must_use]
+    pub fn rgb_only_option(&self) -> Option<&'static str> {
+        [
+            ("lock", !self.lock.is_empty()),
+            ("preserve_transparent_rgb", self.preserve_transparent_rgb),
+            ("opaque_bounds_only", self.opaque_bounds_only),
+            ("dither", self.dither),
+        ]
+        .into_iter()
+        .find_map(|(name, used)| used.then_some(name))
+    }
+}
+
+/// Replaces every pixel near a source color with a target color.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ColorMapping {
+    /// The RGB components of the source color.
+    pub from: [u8; 3],
+    /// The RGB components of the target color.
+    pub to: [u8; 3],
+    /// The largest distance between a pixel and the source color for it to be replaced, measured using the color's
+    /// distance metric.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub tolerance: f32,
+}
+
+/// Describes how the distance between two colors is measured.
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DistanceMetric {
+    /// The straight-line distance between the RGB components, out of 255 for each channel.
+    #[default]
+    Euclidean,
+    /// The distance between the RGB components, where each channel is weighted by how sensitive the eye is to it.
+    Weighted,
+    /// The straight-line distance within the CIE L*a*b* color space, known as Delta E 1976.
+    Cie76,
+    /// The CIEDE2000 color difference, which most closely matches how different two colors appear.
+    Ciede2000,
+}
+
+/// Describes when a color's filters are applied relative to replacing the image's hue.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterStage {
+    /// Filters are applied before the hue is replaced, so they adjust the source image. Any change to the hue itself is
+    /// overwritten by the replacement.
+    BeforeHue,
+    /// Filters are applied after the hue is replaced, so they adjust the recolored image.
+    #[default]
+    AfterHue,
+}
+
+/// Describes how colors that fall outside of the sRGB gamut are brought back into it.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GamutMapping {
+    /// Each channel is clamped independently, which may shift the color's hue.
+    #[default]
+    Clip,
+    /// The color is moved towards the achromatic axis until it fits, which preserves its hue.
+    Compress,
+}
+
+/// Describes how channels are rounded when they are quantized to 8 bits.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rounding {
+    /// Rounds to the nearest value, with halfway values rounded away from zero.
+    #[default]
+    Round,
+    /// Rounds down.
+    Floor,
+    /// Rounds up.
+    Ceil,
+    /// Rounds to the nearest value, with halfway values rounded to the nearest even value.
+    NearestEven,
+}
+
+/// The maximum number of passes that a single filter may apply.
+pub const MAX_FILTER_PASSES: u32 = 64;
+
 /// A color filter.
+///
+/// # Examples
+///
+/// Filters are usually created through their constructors, which only expose valid combinations.
+///
+/// ```
+/// use amethyst_colorizer::config::{
+///     Condition, Filter, FilterOperation, FilterTarget, FilterType,
+/// };
+///
+/// let filter = Filter::image_contrast_add(0.25);
+///
+/// assert_eq!(filter.kind, FilterType::Image);
+/// assert_eq!((filter.target, filter.operation), (FilterTarget::Contrast, FilterOperation::Add));
+/// assert_eq!(filter.value, 0.25);
+/// assert_eq!(filter.passes, 1);
+///
+/// let dark = Condition { max_value: Some(0.5), ..Condition::default() };
+/// let filter = Filter::pixel_hue_set(270.0).with_passes(2).with_condition(dark);
+///
+/// assert_eq!(filter.kind, FilterType::Pixel);
+/// assert_eq!((filter.target, filter.operation), (FilterTarget::Hue, FilterOperation::Set));
+/// assert_eq!((filter.passes, filter.condition), (2, dark));
+/// ```
 #[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct Filter {
     /// The type of filter.
@@ -69,6 +854,220 @@ pub struct Filter {
     pub operation: FilterOperation,
     /// The color filter value.
     pub value: f32,
+    /// The seed used by randomized filters.
+    #[serde(default, skip_serializing_if = "check_u64::<0>")]
+    pub seed: u64,
+    /// The number of times that the filter is applied. Must be within `1..=MAX_FILTER_PASSES`.
+    #[serde(default = "get_u32::<1>", skip_serializing_if = "check_u32::<1>")]
+    pub passes: u32,
+    /// The condition that each pixel must meet for the filter to affect it. Only affects per-pixel operations.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub condition: Condition,
+    /// Whether the filter's effect is scaled by each pixel's alpha. Only affects pixel filters.
+    #[serde(default, skip_serializing_if = "check_bool::<false>")]
+    pub alpha_weighted: bool,
+    /// The hue, in degrees, that is replaced by selective hue filters.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub source_hue: f32,
+    /// The largest distance, in degrees, between a pixel's hue and the source hue for it to be replaced by selective
+    /// hue filters.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub tolerance: f32,
+}
+
+macro_rules! filter_constructors {
+    ($($(#[$attribute:meta])* $name:ident => $kind:ident, $target:ident, $operation:ident;)*) => {$(
+        $(#[$attribute])*
+        #[must_use]
+        pub const fn $name(value: f32) -> Self {
+            Self::new(FilterType::$kind, FilterTarget::$target, FilterOperation::$operation, value)
+        }
+    )*};
+}
+
+impl Filter {
+    filter_constructors! {
+        /// Creates a pixel filter that rotates each pixel's hue by the given number of degrees.
+        pixel_hue_add => Pixel, Hue, Add;
+        /// Creates a pixel filter that scales each pixel's hue by the given factor.
+        pixel_hue_multiply => Pixel, Hue, Multiply;
+        /// Creates a pixel filter that sets each pixel's hue to the given number of degrees.
+        pixel_hue_set => Pixel, Hue, Set;
+        /// Creates a pixel filter that adds the given amount to each pixel's saturation.
+        pixel_saturation_add => Pixel, Saturation, Add;
+        /// Creates a pixel filter that scales each pixel's saturation by the given factor.
+        pixel_saturation_multiply => Pixel, Saturation, Multiply;
+        /// Creates a pixel filter that sets each pixel's saturation to the given value.
+        pixel_saturation_set => Pixel, Saturation, Set;
+        /// Creates a pixel filter that adds the given amount to each pixel's brightness.
+        pixel_brightness_add => Pixel, Brightness, Add;
+        /// Creates a pixel filter that scales each pixel's brightness by the given factor.
+        pixel_brightness_multiply => Pixel, Brightness, Multiply;
+        /// Creates a pixel filter that sets each pixel's brightness to the given value.
+        pixel_brightness_set => Pixel, Brightness, Set;
+        /// Creates an image filter that adjusts the image's contrast by the given amount.
+        image_contrast_add => Image, Contrast, Add;
+        /// Creates an image filter that scales the image's contrast by the given factor.
+        image_contrast_multiply => Image, Contrast, Multiply;
+        /// Creates an image filter that rotates the image's hue by the given number of degrees.
+        image_hue_add => Image, Hue, Add;
+        /// Creates an image filter that sets the image's hue to the given number of degrees.
+        image_hue_set => Image, Hue, Set;
+        /// Creates an image filter that adds the given amount to the image's saturation.
+        image_saturation_add => Image, Saturation, Add;
+        /// Creates an image filter that scales the image's saturation by the given factor.
+        image_saturation_multiply => Image, Saturation, Multiply;
+        /// Creates an image filter that sets the image's saturation to the given value.
+        image_saturation_set => Image, Saturation, Set;
+        /// Creates an image filter that adds the given amount to the image's brightness, out of 255.
+        image_brightness_add => Image, Brightness, Add;
+        /// Creates an image filter that reduces the image to at most the given number of colors.
+        image_quantize_set => Image, Quantize, Set;
+        /// Creates an image filter that blurs the image with the given standard deviation, in pixels.
+        image_blur_set => Image, Blur, Set;
+        /// Creates an image filter that darkens the image towards its corners with the given strength.
+        image_vignette_set => Image, Vignette, Set;
+    }
+
+    /// Creates a filter with the given components, using the default value for every other field.
+    ///
+    /// This does not check whether the combination is valid; prefer the more specific constructors where possible.
+    #[must_use]
+    pub const fn new(kind: FilterType, target: FilterTarget, operation: FilterOperation, value: f32) -> Self {
+        Self {
+            kind,
+            target,
+            operation,
+            value,
+            seed: 0,
+            passes: 1,
+            condition: Condition {
+                min_hue: None,
+                max_hue: None,
+                min_saturation: None,
+                max_saturation: None,
+                min_value: None,
+                max_value: None,
+            },
+            alpha_weighted: false,
+            source_hue: 0.0,
+            tolerance: 0.0,
+        }
+    }
+
+    /// Creates an image filter that adds seeded noise to the image's saturation and brightness, scaled by the given
+    /// amplitude.
+    #[must_use]
+    pub const fn image_noise_add(amplitude: f32, seed: u64) -> Self {
+        Self { seed, ..Self::new(FilterType::Image, FilterTarget::Noise, FilterOperation::Add, amplitude) }
+    }
+
+    /// Creates an image filter that sets the hue of every pixel within the given tolerance of the source hue, all in
+    /// degrees.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use amethyst_colorizer::config::Filter;
+    /// use image::{Rgba, RgbaImage};
+    ///
+    /// let mut image = RgbaImage::from_pixel(2, 1, Rgba([0, 0, 255, 255]));
+    ///
+    /// image.put_pixel(0, 0, Rgba([255, 0, 0, 255]));
+    ///
+    /// amethyst_colorizer::apply_image_filter(
+    ///     Filter::image_selective_hue_set(0.0, 15.0, 120.0),
+    ///     &mut image,
+    /// )?;
+    ///
+    /// assert_eq!(image.get_pixel(0, 0).0, [0, 255, 0, 255]);
+    /// assert_eq!(image.get_pixel(1, 0).0, [0, 0, 255, 255]);
+    /// # Ok::<(), amethyst_colorizer::Error>(())
+    /// ```
+    #[must_use]
+    pub const fn image_selective_hue_set(source_hue: f32, tolerance: f32, hue: f32) -> Self {
+        let filter = Self::new(FilterType::Image, FilterTarget::SelectiveHue, FilterOperation::Set, hue);
+
+        Self { source_hue, tolerance, ..filter }
+    }
+
+    /// Returns this filter with the given number of passes.
+    #[must_use]
+    pub const fn with_passes(self, passes: u32) -> Self {
+        Self { passes, ..self }
+    }
+
+    /// Returns this filter with the given condition.
+    #[must_use]
+    pub const fn with_condition(self, condition: Condition) -> Self {
+        Self { condition, ..self }
+    }
+
+    /// Returns this filter with its effect scaled by each pixel's alpha.
+    #[must_use]
+    pub const fn weighted_by_alpha(self) -> Self {
+        Self { alpha_weighted: true, ..self }
+    }
+
+    /// Returns the key that filters are scheduled by, where every pixel filter is applied before any image filter.
+    ///
+    /// The derived [`PartialOrd`] implementation compares every field, so it should not be used for scheduling.
+    #[must_use]
+    pub const fn sort_key(&self) -> FilterType {
+        self.kind
+    }
+}
+
+/// Sorts the given filters into the order that they are applied, grouping them by their type while preserving the
+/// declared order within each group.
+///
+/// This matches the order used by [`crate::transform_image`], so sorting a color's filters never changes its output,
+/// and [`crate::transform_image_staged`] sorts them to decide which stage each filter is applied in.
+pub fn sort_filters(filters: &mut [Filter]) {
+    filters.sort_by_key(Filter::sort_key);
+}
+
+/// A condition on a pixel's current HSV components, where every bound is inclusive and optional.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct Condition {
+    /// The minimum hue, in degrees. If this is larger than the maximum hue, the range wraps around `360.0`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_hue: Option<f32>,
+    /// The maximum hue, in degrees.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_hue: Option<f32>,
+    /// The minimum saturation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_saturation: Option<f32>,
+    /// The maximum saturation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_saturation: Option<f32>,
+    /// The minimum brightness.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_value: Option<f32>,
+    /// The maximum brightness.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_value: Option<f32>,
+}
+
+impl Condition {
+    /// Returns whether the given color meets this condition.
+    #[must_use]
+    pub fn matches(&self, hsv: &Hsv<palette::encoding::Srgb>) -> bool {
+        let within = |value: f32, min: Option<f32>, max: Option<f32>| {
+            min.is_none_or(|min| value >= min) && max.is_none_or(|max| value <= max)
+        };
+
+        let hue = hsv.hue.into_positive_degrees();
+        let hue_matches = match (self.min_hue, self.max_hue) {
+            (Some(min), Some(max)) if min > max => hue >= min || hue <= max,
+            (min, max) => within(hue, min, max),
+        };
+
+        hue_matches
+            && within(hsv.saturation, self.min_saturation, self.max_saturation)
+            && within(hsv.value, self.min_value, self.max_value)
+    }
 }
 
 /// The type of a filter.
@@ -85,14 +1084,30 @@ pub enum FilterType {
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum FilterTarget {
-    /// The hue of the color or image.
+    /// The hue of the color or image. Setting an image's hue recolors every pixel, like the base recolor does.
+    ///
+    /// Adding to a hue rotates it towards larger angles, meaning that red moves towards yellow, then green, then blue.
+    /// Rotations wrap around the color wheel, so adding `-30.0` is equivalent to adding `330.0`.
     Hue,
-    /// The saturation of the color or image.
+    /// The saturation of the color or image. The result of every operation is clamped into `0.0..=1.0`.
     Saturation,
     /// The lightness of the color or image.
     Brightness,
     /// The contrast of the image. Does nothing for pixels.
     Contrast,
+    /// Seeded random noise in the saturation and brightness of the image. Does nothing for pixels.
+    Noise,
+    /// The hue of every pixel near the filter's source hue, which may only be set. Does nothing for pixels.
+    SelectiveHue,
+    /// The number of distinct colors within the image, which may only be set. Each pixel's alpha is kept as-is. Does
+    /// nothing for pixels.
+    Quantize,
+    /// A Gaussian blur of the image, where the value is its standard deviation in pixels, which may only be set.
+    /// Transparent pixels do not darken the edges of the blurred area. Does nothing for pixels.
+    Blur,
+    /// A darkening of the image towards its corners, where the value is its strength, which may only be set. Each
+    /// pixel's alpha is kept as-is. Does nothing for pixels.
+    Vignette,
 }
 
 /// Describes how to apply a filter's value.
@@ -102,11 +1117,30 @@ pub enum FilterOperation {
     /// The value is added to the base value.
     Add,
     /// The value is multiplied by the base value.
+    ///
+    /// For hues, the base value is first normalized into `0.0..360.0` degrees, and the result wraps back into the same
+    /// range. For example, multiplying a hue of `350.0` by `2.0` results in a hue of `340.0`.
     Multiply,
     /// The value is set.
     Set,
 }
 
+/// Returns whether the given value is equal to its default.
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+/// Returns one, used as the default value of scaling factors.
+const fn get_one() -> f32 {
+    1.0
+}
+
+/// Returns whether the given scaling factor is exactly one.
+#[allow(clippy::float_cmp, clippy::trivially_copy_pass_by_ref)]
+fn is_one(value: &f32) -> bool {
+    *value == 1.0
+}
+
 macro_rules! simple_const_get {
     ($($name:ident as $type:ty),* $(,)?) => {$(
         #[allow(unused)]
@@ -149,3 +1183,356 @@ simple_const_check![
     check_i64 as i64,
     check_i128 as i128,
 ];
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// Returns the default configuration.
+    fn default_config() -> Result<Config> {
+        Config::from_path(&Path::new(env!("CARGO_MANIFEST_DIR")).join("res/default.json"))
+    }
+
+    /// Returns an empty scratch directory with the given name.
+    fn scratch_dir(name: &str) -> Result<PathBuf> {
+        let directory = std::env::temp_dir().join("amethyst-colorizer-tests").join(name);
+
+        if directory.try_exists()? {
+            std::fs::remove_dir_all(&directory)?;
+        }
+
+        std::fs::create_dir_all(&directory)?;
+
+        Ok(directory)
+    }
+
+    /// Returns a configuration where every field of a color is set to a value other than its default.
+    fn populated_config() -> Result<Config> {
+        let mut config = self::default_config()?;
+        let filter = Filter {
+            seed: 7,
+            passes: 2,
+            condition: Condition { min_hue: Some(200.0), max_value: Some(0.9), ..Condition::default() },
+            alpha_weighted: true,
+            source_hue: 270.0,
+            tolerance: 15.0,
+            ..Filter::image_selective_hue_set(0.0, 0.0, 300.0)
+        };
+        let color = DyeColorConfig {
+            allow_alpha: false,
+            filters: [filter, Filter::pixel_hue_add(10.0)].into(),
+            gamut_mapping: GamutMapping::Compress,
+            rounding: Rounding::Floor,
+            dither: true,
+            color_map: [ColorMapping { from: [1, 2, 3], to: [4, 5, 6], tolerance: 8.0 }].into(),
+            distance_metric: DistanceMetric::Ciede2000,
+            alpha_scale: 0.5,
+            chroma_key: Some([0, 255, 0]),
+            chroma_key_tolerance: 4,
+            lock: [FilterTarget::Brightness].into(),
+            rgb_blend: Some(0.25),
+            filters_enabled: false,
+            min_saturation: 0.1,
+            output_name: Some("violet".to_string()),
+            preserve_transparent_rgb: true,
+            hue_correction: -5.0,
+            opaque_bounds_only: true,
+            filter_stage: FilterStage::BeforeHue,
+            ..DyeColorConfig::new([137, 50, 184])
+        };
+
+        config.colors.insert(DyeColor::Purple, color);
+        config.output_depth = Some(BitDepth::Sixteen);
+        config.presets.insert("dim".to_string(), [Filter::pixel_brightness_multiply(0.5)].into());
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    #[test]
+    fn default_config_round_trips() -> Result<()> {
+        let config = self::default_config()?;
+
+        assert_eq!(serde_json::from_str::<Config>(&config.to_pretty_json()?)?, config);
+
+        Ok(())
+    }
+
+    #[test]
+    fn populated_config_round_trips() -> Result<()> {
+        let config = self::populated_config()?;
+
+        assert_eq!(serde_json::from_str::<Config>(&config.to_pretty_json()?)?, config);
+
+        Ok(())
+    }
+
+    #[test]
+    fn defaults_are_omitted_and_restored() -> Result<()> {
+        let color = DyeColorConfig::new([137, 50, 184]);
+        let json = serde_json::to_string(&color)?;
+
+        assert_eq!(json, r#"{"rgb":[137,50,184]}"#);
+        assert_eq!(serde_json::from_str::<DyeColorConfig>(&json)?, color);
+
+        Ok(())
+    }
+
+    #[test]
+    fn hsv_targets_round_trip() -> Result<()> {
+        let json = r#"{ "colors": { "purple": { "hsv": [280.0, 0.75, 0.75] } } }"#;
+        let config = serde_json::from_str::<Config>(json)?;
+
+        assert_eq!(config.colors[&DyeColor::Purple].rgb, [143, 48, 191]);
+        assert_eq!(serde_json::from_str::<Config>(&config.to_pretty_json()?)?, config);
+
+        Ok(())
+    }
+
+    #[test]
+    fn hsv_targets_require_the_nearest_rgb_value() -> Result<()> {
+        let mut config = DyeColorConfig::new([0, 0, 0]);
+
+        config.hsv = Some([280.0, 0.75, 0.75]);
+
+        assert!(matches!(config.validate(DyeColor::Purple), Err(Error::InvalidTarget(DyeColor::Purple))));
+
+        config.rgb = [143, 48, 191];
+
+        config.validate(DyeColor::Purple)
+    }
+
+    #[test]
+    fn presets_round_trip_expanded() -> Result<()> {
+        let json = r#"{
+            "colors": { "purple": { "rgb": [137, 50, 184], "filters": [{ "preset": "dim" }] } },
+            "presets": { "dim": [{ "type": "pixel", "target": "brightness", "operation": "multiply", "value": 0.5 }] }
+        }"#;
+        let config = serde_json::from_str::<Config>(json)?;
+        let serialized = config.to_pretty_json()?;
+
+        assert!(!serialized.contains(r#""preset":"#));
+        assert_eq!(serde_json::from_str::<Config>(&serialized)?, config);
+
+        Ok(())
+    }
+
+    #[test]
+    fn fragments_round_trip() -> Result<()> {
+        let config = self::populated_config()?;
+        let fragment = config.to_fragment()?;
+
+        assert!(fragment.starts_with(&format!("{FRAGMENT_VERSION}.")));
+        assert_eq!(Config::from_fragment(&fragment)?, config);
+
+        let color = &config.colors[&DyeColor::Purple];
+
+        assert_eq!(&DyeColorConfig::from_fragment(&color.to_fragment()?)?, color);
+
+        Ok(())
+    }
+
+    #[test]
+    fn fragments_with_other_versions_are_rejected() -> Result<()> {
+        let fragment = self::default_config()?.to_fragment()?;
+        let (_, data) = fragment.split_once('.').unwrap_or_default();
+
+        assert!(matches!(Config::from_fragment(&format!("0.{data}")), Err(Error::FragmentVersion(v)) if v == "0"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn non_finite_filters_are_rejected() {
+        let filters = [
+            Filter::pixel_hue_add(f32::NAN),
+            Filter::image_contrast_add(f32::INFINITY),
+            Filter::image_selective_hue_set(f32::NEG_INFINITY, 10.0, 0.0),
+            Filter::image_selective_hue_set(0.0, f32::NAN, 0.0),
+        ];
+
+        for filter in filters {
+            let config = DyeColorConfig { filters: [filter].into(), ..DyeColorConfig::new([137, 50, 184]) };
+
+            assert!(matches!(config.validate(DyeColor::Purple), Err(Error::NonFiniteValue(..))), "{filter:?}");
+        }
+    }
+
+    #[test]
+    fn output_names_must_stay_within_the_output_directory() {
+        for name in ["", ".", "../escaped", "nested/name", "nested\\name", "name.."] {
+            let config = DyeColorConfig { output_name: Some(name.to_string()), ..DyeColorConfig::new([137, 50, 184]) };
+
+            assert!(matches!(config.validate(DyeColor::Purple), Err(Error::InvalidOutputName(..))), "{name:?}");
+        }
+
+        let output_name = Some("violet.v2".to_string());
+        let config = DyeColorConfig { output_name, ..DyeColorConfig::new([137, 50, 184]) };
+
+        assert!(config.validate(DyeColor::Purple).is_ok());
+    }
+
+    #[test]
+    fn filter_errors_are_reported_as_is() {
+        let json = r#"{ "colors": { "purple": { "rgb": [137, 50, 184], "filters": [
+            { "type": "pixel", "target": "hu", "operation": "add", "value": 10.0 }
+        ] } } }"#;
+
+        let error = serde_json::from_str::<Config>(json).err().map(|e| e.to_string()).unwrap_or_default();
+
+        assert!(error.starts_with("unknown variant `hu`, expected one of `hue`"), "{error}");
+    }
+
+    #[test]
+    fn preset_references_are_expanded() -> Result<()> {
+        let json = r#"{
+            "colors": { "purple": { "rgb": [137, 50, 184], "filters": [{ "preset": "dim" }] } },
+            "presets": { "dim": [{ "type": "pixel", "target": "brightness", "operation": "multiply", "value": 0.5 }] }
+        }"#;
+
+        let config = serde_json::from_str::<Config>(json)?;
+
+        assert_eq!(&*config.colors[&DyeColor::Purple].filters, &[Filter::pixel_brightness_multiply(0.5)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn achromatic_colors_are_rejected_unless_unused() {
+        let gray = DyeColorConfig::new([128, 128, 128]);
+
+        assert!(matches!(
+            gray.validate(DyeColor::Gray),
+            Err(Error::AchromaticColor(DyeColor::Gray, [128, 128, 128]))
+        ));
+
+        let blended = DyeColorConfig { rgb_blend: Some(0.5), ..gray };
+
+        assert!(blended.validate(DyeColor::Gray).is_ok());
+        assert!(DyeColorConfig::new([128, 128, 129]).validate(DyeColor::Gray).is_ok());
+    }
+
+    #[test]
+    fn pretty_json_lists_colors_in_declaration_order() -> Result<()> {
+        let config = self::default_config()?;
+        let json = config.to_pretty_json()?;
+        let positions = DyeColor::ALL.map(|color| json.find(&format!("\"{color}\": {{")));
+
+        assert!(positions.iter().all(Option::is_some), "{json}");
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(json, config.to_pretty_json()?);
+        assert_eq!(serde_json::from_str::<Config>(&json)?, config);
+
+        Ok(())
+    }
+
+    #[test]
+    fn output_depths_must_be_eight_or_sixteen_bits() -> Result<()> {
+        let parse = |depth: u8| serde_json::from_value::<Config>(json!({ "colors": {}, "output_depth": depth }));
+
+        assert_eq!(parse(8)?.output_depth, Some(BitDepth::Eight));
+        assert_eq!(parse(16)?.output_depth, Some(BitDepth::Sixteen));
+        assert!(parse(12).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn filter_files_are_loaded_relative_to_their_reference() -> Result<()> {
+        let directory = self::scratch_dir("filter_files")?;
+        let filters = json!([{ "type": "pixel", "target": "brightness", "operation": "multiply", "value": 0.5 }]);
+        let config = |filters: &Value| json!({ "colors": { "purple": { "rgb": [137, 50, 184], "filters": filters } } });
+
+        std::fs::create_dir_all(directory.join("filters"))?;
+        std::fs::write(directory.join("shared.json"), filters.to_string())?;
+        std::fs::write(directory.join("filters/dim.json"), json!({ "$ref": "../shared.json" }).to_string())?;
+        std::fs::write(directory.join("config.json"), config(&json!({ "$ref": "filters/dim.json" })).to_string())?;
+
+        let loaded = Config::from_path(&directory.join("config.json"))?;
+
+        assert_eq!(&*loaded.colors[&DyeColor::Purple].filters, &[Filter::pixel_brightness_multiply(0.5)]);
+        assert_eq!(loaded, serde_json::from_value(config(&filters))?);
+
+        std::fs::write(directory.join("missing.json"), config(&json!({ "$ref": "missing/dim.json" })).to_string())?;
+
+        let result = Config::load(&directory.join("missing.json"));
+
+        assert!(matches!(result, Err(Error::MissingFilterFile(path)) if path.ends_with("missing/dim.json")));
+
+        std::fs::write(directory.join("first.json"), json!({ "$ref": "second.json" }).to_string())?;
+        std::fs::write(directory.join("second.json"), json!({ "$ref": "first.json" }).to_string())?;
+        std::fs::write(directory.join("cycle.json"), config(&json!({ "$ref": "first.json" })).to_string())?;
+
+        let result = Config::load(&directory.join("cycle.json"));
+
+        assert!(matches!(result, Err(Error::FilterFileCycle(path)) if path.ends_with("first.json")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn sorting_filters_groups_them_by_type() {
+        let mut filters = [
+            Filter::image_contrast_add(10.0),
+            Filter::pixel_saturation_multiply(0.5),
+            Filter::image_blur_set(1.0),
+            Filter::pixel_brightness_add(0.1),
+            Filter::image_contrast_add(-5.0),
+        ];
+
+        self::sort_filters(&mut filters);
+
+        assert_eq!(
+            filters,
+            [
+                Filter::pixel_saturation_multiply(0.5),
+                Filter::pixel_brightness_add(0.1),
+                Filter::image_contrast_add(10.0),
+                Filter::image_blur_set(1.0),
+                Filter::image_contrast_add(-5.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn configs_are_loaded_from_every_format() -> Result<()> {
+        let directory = self::scratch_dir("config_formats")?;
+        let filter = json!({ "type": "pixel", "target": "saturation", "operation": "set", "value": 0.0 });
+        let expected: Config = serde_json::from_value(json!({
+            "colors": { "purple": { "rgb": [137, 50, 184], "filters": [filter] } }
+        }))?;
+
+        let toml = r#"
+            [colors.purple]
+            rgb = [137, 50, 184]
+            filters = [{ type = "pixel", target = "saturation", operation = "set", value = 0.0 }]
+        "#;
+        let yaml = "
+            colors:
+              purple:
+                rgb: [137, 50, 184]
+                filters:
+                  - { type: pixel, target: saturation, operation: set, value: 0.0 }
+        ";
+
+        for (name, text) in [("config.toml", toml), ("config.yaml", yaml)] {
+            std::fs::write(directory.join(name), text)?;
+
+            assert_eq!(Config::from_path(&directory.join(name))?, expected, "{name}");
+        }
+
+        let invalid = json!({ "colors": { "purple": { "rgb": [137, 50, 184], "min_saturation": 2.0 } } });
+
+        std::fs::write(directory.join("invalid.json"), invalid.to_string())?;
+
+        assert!(Config::load(&directory.join("invalid.json")).is_ok());
+        assert!(matches!(
+            Config::from_path(&directory.join("invalid.json")),
+            Err(Error::InvalidMinSaturation(DyeColor::Purple, saturation)) if (saturation - 2.0).abs() < f32::EPSILON
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+use image::{Rgba, RgbaImage};
+
+/// The width of each glyph, in pixels.
+pub const GLYPH_WIDTH: u32 = 3;
+/// The height of each glyph, in pixels.
+pub const GLYPH_HEIGHT: u32 = 5;
+/// The horizontal space between each glyph, in pixels.
+pub const GLYPH_SPACING: u32 = 1;
+/// The height of a single line of text, including a pixel of padding above and below it.
+pub const LINE_HEIGHT: u32 = GLYPH_HEIGHT + 2;
+
+/// Returns the rows of the given character's glyph, where the lowest three bits of each row are drawn from left to
+/// right.
+///
+/// Letters are always drawn in uppercase, and any unsupported character is drawn as a space.
+const fn glyph(character: char) -> [u8; GLYPH_HEIGHT as usize] {
+    match character.to_ascii_uppercase() {
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+        _ => [0b000; GLYPH_HEIGHT as usize],
+    }
+}
+
+/// Returns the width of the given text when drawn, in pixels.
+pub fn text_width(text: &str) -> u32 {
+    let count = u32::try_from(text.chars().count()).unwrap_or(u32::MAX);
+
+    count.saturating_mul(GLYPH_WIDTH + GLYPH_SPACING).saturating_sub(GLYPH_SPACING)
+}
+
+/// Draws the given text onto an image with its top-left corner at the given position.
+///
+/// Any part of the text that falls outside of the image is skipped.
+pub fn draw_text(image: &mut RgbaImage, text: &str, (x, y): (u32, u32), color: Rgba<u8>) {
+    for (index, character) in (0_u32 ..).zip(text.chars()) {
+        let left = x.saturating_add(index.saturating_mul(GLYPH_WIDTH + GLYPH_SPACING));
+
+        for (row, bits) in (0_u32 ..).zip(self::glyph(character)) {
+            for column in (0 .. GLYPH_WIDTH).filter(|column| (bits >> (GLYPH_WIDTH - 1 - column)) & 1 == 1) {
+                if let Some(pixel) = image.get_pixel_mut_checked(left.saturating_add(column), y.saturating_add(row)) {
+                    *pixel = color;
+                }
+            }
+        }
+    }
+}
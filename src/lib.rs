@@ -16,19 +16,39 @@
 // <https://www.gnu.org/licenses/>.
 
 //! A small utility website that automatically converts amethyst into their dyeable variants.
+//!
+//! Every function within this crate is synchronous and CPU-bound, including decoding, transforming, and encoding
+//! images. Callers running on an asynchronous runtime should move this work onto a blocking thread pool, such as with
+//! `tokio::task::spawn_blocking`, to avoid stalling the executor while large images are processed.
 #![deny(clippy::expect_used, clippy::panic, clippy::unwrap_used, unsafe_code)]
 #![warn(clippy::nursery, clippy::todo, clippy::pedantic, missing_docs)]
 #![allow(clippy::module_name_repetitions)]
 
-use config::{DyeColorConfig, Filter, FilterOperation, FilterTarget, FilterType};
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+
+use color_quant::NeuQuant;
+use config::{
+    ColorMapping, Config, DistanceMetric, DyeColor, DyeColorConfig, Filter, FilterOperation, FilterStage, FilterTarget,
+    FilterType, GamutMapping, Rounding,
+};
 use image::{
-    imageops::colorops::{brighten_in_place, contrast_in_place, huerotate_in_place},
-    Pixel, RgbaImage,
+    imageops::colorops::{brighten_in_place, contrast_in_place},
+    math::Rect,
+    GenericImage, GenericImageView, GrayAlphaImage, GrayImage, ImageBuffer, ImageFormat, Luma, Pixel, Rgb, RgbImage,
+    Rgba, RgbaImage,
 };
-use palette::{FromColor, GetHue, Hsv, Hsva, IntoColor, SaturateAssign, SetHue, ShiftHueAssign, Srgb, Srgba};
+use palette::color_difference::{Ciede2000, DeltaE};
+use palette::convert::IntoColorUnclamped;
+use palette::{Clamp, FromColor, GetHue, Hsv, Hsva, Lab, RgbHue, SaturateAssign, SetHue, ShiftHueAssign, Srgb, Srgba};
 
 /// Defines the library's configuration file.
 pub mod config;
+/// Defines a tiny bitmap font used to label images.
+mod font;
+
+/// The default base amethyst texture, encoded as a PNG.
+pub const DEFAULT_BASE_IMAGE: &[u8] = include_bytes!("../res/amethyst.png");
 
 /// A result type returned by the library.
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -39,6 +59,93 @@ pub enum Error {
     /// A filter was given an invalid type and operator combination.
     #[error("invalid operation '{0:?}', '{1:?}', '{2:?}'")]
     InvalidFilter(FilterType, FilterTarget, FilterOperation),
+    /// A filter was given a value that is either NaN or infinite.
+    #[error("non-finite value '{3}' for operation '{0:?}', '{1:?}', '{2:?}'")]
+    NonFiniteValue(FilterType, FilterTarget, FilterOperation, f32),
+    /// A filter was given a number of passes that is either zero or too large.
+    #[error("invalid pass count '{2}' for filter #{1} of color '{0}'")]
+    InvalidPasses(DyeColor, usize, u32),
+    /// A color was given an achromatic RGB value, which has no hue to apply.
+    #[error("color '{0}' has an achromatic rgb value '{1:?}' with no hue, consider using a more saturated value")]
+    AchromaticColor(DyeColor, [u8; 3]),
+    /// A color was given an RGB blend factor that is outside of 0.0 and 1.0.
+    #[error("invalid rgb blend factor '{1}' for color '{0}'")]
+    InvalidBlendFactor(DyeColor, f32),
+    /// A color was given a minimum saturation that is outside of 0.0 and 1.0.
+    #[error("invalid minimum saturation '{1}' for color '{0}'")]
+    InvalidMinSaturation(DyeColor, f32),
+    /// A color was given a hue correction that is either NaN or infinite.
+    #[error("non-finite hue correction '{1}' for color '{0}'")]
+    NonFiniteHueCorrection(DyeColor, f32),
+    /// A color was given neither an RGB nor an HSV target, or was given both and they disagree.
+    #[error("color '{0}' must have either an 'rgb' value or an equivalent 'hsv' value")]
+    InvalidTarget(DyeColor),
+    /// A color was given an HSV target with a non-finite hue, or a saturation or value outside of 0.0 and 1.0.
+    #[error("invalid hsv target '{1:?}' for color '{0}'")]
+    InvalidHsv(DyeColor, [f32; 3]),
+    /// A color mapping was given a tolerance that is either negative, NaN, or infinite.
+    #[error("invalid tolerance '{2}' for color mapping #{1} of color '{0}'")]
+    InvalidMapTolerance(DyeColor, usize, f32),
+    /// A color was given an alpha scale that is either negative, NaN, or infinite.
+    #[error("invalid alpha scale '{1}' for color '{0}'")]
+    InvalidAlphaScale(DyeColor, f32),
+    /// A color was given an output name that is empty or could refer to a file outside of the output directory.
+    #[error("invalid output name '{1}' for color '{0}'")]
+    InvalidOutputName(DyeColor, String),
+    /// A filter referenced a preset that does not exist.
+    #[error("unknown filter preset '{0}'")]
+    UnknownPreset(String),
+    /// A filter preset references itself, either directly or through other presets.
+    #[error("filter preset '{0}' references itself")]
+    PresetCycle(String),
+    /// A grayscale image was given a configuration with an option that is only supported for RGB images.
+    #[error("the '{0}' option is not supported for grayscale images")]
+    UnsupportedGrayOption(&'static str),
+    /// A grayscale image was given a configuration that recolors its pixels, which requires an RGB image.
+    #[error("the configuration recolors pixels, which is not supported for grayscale images")]
+    RecolorsGrayscale,
+    /// An image was given with a width or height of zero.
+    #[error("image has an empty size of {0}x{1}")]
+    EmptyImage(u32, u32),
+    /// A referenced filter file does not exist.
+    #[error("unable to find the filter file at {0:?}")]
+    MissingFilterFile(std::path::PathBuf),
+    /// A referenced filter file references itself, either directly or through other files.
+    #[error("filter file at {0:?} references itself")]
+    FilterFileCycle(std::path::PathBuf),
+    /// A mask's dimensions do not match those of its image.
+    #[error("mask dimensions {0:?} do not match image dimensions {1:?}")]
+    MaskDimensions((u32, u32), (u32, u32)),
+    /// A destination image's dimensions do not match those of its source image.
+    #[error("destination dimensions {0:?} do not match source dimensions {1:?}")]
+    DestinationDimensions((u32, u32), (u32, u32)),
+    /// An original image's dimensions do not match those of its transformed image.
+    #[error("original dimensions {0:?} do not match image dimensions {1:?}")]
+    OriginalDimensions((u32, u32), (u32, u32)),
+    /// A palette layout does not have exactly one cell per dye color, or has cells smaller than a pixel.
+    #[error("invalid palette layout of {0}x{1} cells")]
+    InvalidPaletteLayout(u32, u32),
+    /// An image could not be decoded or encoded.
+    #[error(transparent)]
+    Image(#[from] image::ImageError),
+    /// A file could not be read.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// A configuration could not be serialized or deserialized.
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    /// A TOML configuration could not be deserialized.
+    #[error(transparent)]
+    Toml(#[from] toml::de::Error),
+    /// A YAML configuration could not be deserialized.
+    #[error(transparent)]
+    Yaml(#[from] serde_yaml::Error),
+    /// A shareable configuration fragment could not be decoded.
+    #[error(transparent)]
+    Base64(#[from] base64::DecodeError),
+    /// A shareable configuration fragment was created with an unsupported version.
+    #[error("unsupported configuration fragment version '{0}'")]
+    FragmentVersion(String),
 }
 
 impl Error {
@@ -47,107 +154,2736 @@ impl Error {
     pub const fn invalid_filter(filter: Filter) -> Self {
         Self::InvalidFilter(filter.kind, filter.target, filter.operation)
     }
+
+    /// Creates a non-finite value error.
+    #[must_use]
+    pub const fn non_finite_value(filter: Filter) -> Self {
+        Self::NonFiniteValue(filter.kind, filter.target, filter.operation, filter.value)
+    }
+}
+
+/// Returns the image formats that the library is able to decode.
+#[must_use]
+pub const fn supported_input_formats() -> &'static [ImageFormat] {
+    &[ImageFormat::Png, ImageFormat::Tga]
+}
+
+/// Returns the image formats that the library is able to encode.
+///
+/// Note that TGA images may only be encoded with 8-bit channels.
+#[must_use]
+pub const fn supported_output_formats() -> &'static [ImageFormat] {
+    &[ImageFormat::Png, ImageFormat::Tga]
+}
+
+/// Guesses the format of the given encoded image from its contents.
+///
+/// This returns `None` if the format could not be recognized. Note that a recognized format is not necessarily
+/// supported; see [`supported_input_formats`].
+#[must_use]
+pub fn detect_format(bytes: &[u8]) -> Option<ImageFormat> {
+    image::guess_format(bytes).ok()
+}
+
+/// Decodes and returns the default base amethyst texture.
+///
+/// # Errors
+///
+/// This function may return an error if the embedded texture could not be decoded.
+pub fn default_base_image() -> Result<RgbaImage> {
+    Ok(image::load_from_memory_with_format(DEFAULT_BASE_IMAGE, ImageFormat::Png)?.to_rgba8())
+}
+
+/// Describes how pixels are converted between their stored and HSVA representations.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Codec {
+    /// The strategy used to bring out-of-gamut colors back into sRGB.
+    gamut_mapping: GamutMapping,
+    /// The rounding mode used when quantizing channels to 8 bits.
+    rounding: Rounding,
+    /// Whether quantization errors are diffused into neighboring pixels.
+    dither: bool,
+}
+
+impl Codec {
+    /// Returns the codec described by the given color configuration.
+    const fn of(config: &DyeColorConfig) -> Self {
+        Self { gamut_mapping: config.gamut_mapping, rounding: config.rounding, dither: config.dither }
+    }
+
+    /// Quantizes the given channel into 8 bits.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn quantize(self, channel: f32) -> u8 {
+        let scaled = (channel * 255.0).clamp(0.0, 255.0);
+
+        (match self.rounding {
+            Rounding::Round => scaled.round(),
+            Rounding::Floor => scaled.floor(),
+            Rounding::Ceil => scaled.ceil(),
+            Rounding::NearestEven => scaled.round_ties_even(),
+        }) as u8
+    }
+
+    /// Maps the given color into the sRGB gamut.
+    fn map_gamut(self, rgba: Srgba<f32>) -> Srgba<f32> {
+        match self.gamut_mapping {
+            GamutMapping::Clip => rgba.clamp(),
+            GamutMapping::Compress => self::compress_gamut(rgba),
+        }
+    }
+}
+
+/// Tracks the quantization error of each color channel using Floyd-Steinberg diffusion.
+///
+/// Each pixel's error is spread across the pixels to its right and below it, so that smooth gradients are dithered
+/// rather than banded. Pixels must be quantized in row-major order.
+struct ErrorDiffusion {
+    /// The error carried into each pixel of the current row, with an extra column on either side.
+    current: Vec<[f32; 3]>,
+    /// The error carried into each pixel of the next row, with an extra column on either side.
+    next: Vec<[f32; 3]>,
+}
+
+impl ErrorDiffusion {
+    /// Creates a new error diffusion for an image with the given width.
+    fn new(width: usize) -> Self {
+        Self { current: vec![[0.0; 3]; width + 2], next: vec![[0.0; 3]; width + 2] }
+    }
+
+    /// Quantizes the RGB channels of the pixel at the given column, diffusing any error into its neighbors.
+    fn quantize(&mut self, codec: Codec, x: usize, rgb: [f32; 3]) -> [u8; 3] {
+        if x == 0 {
+            std::mem::swap(&mut self.current, &mut self.next);
+
+            self.next.fill([0.0; 3]);
+        }
+
+        // Every index is offset by the extra column on the left.
+        let x = x + 1;
+        let mut output = [0; 3];
+
+        for (channel, value) in rgb.into_iter().enumerate() {
+            let value = value + self.current[x][channel];
+            let quantized = codec.quantize(value);
+            let error = value.clamp(0.0, 1.0) - (f32::from(quantized) / 255.0);
+
+            self.current[x + 1][channel] += error * 7.0 / 16.0;
+            self.next[x - 1][channel] += error * 3.0 / 16.0;
+            self.next[x][channel] += error * 5.0 / 16.0;
+            self.next[x + 1][channel] += error / 16.0;
+
+            output[channel] = quantized;
+        }
+
+        output
+    }
+}
+
+/// Moves the given color towards the achromatic axis until every channel fits within the sRGB gamut.
+///
+/// Since every channel is scaled towards the same gray value, the ratios between channels (and therefore the hue) are
+/// preserved.
+fn compress_gamut(rgba: Srgba<f32>) -> Srgba<f32> {
+    let (red, green, blue, alpha) = rgba.into_components();
+    let gray = 0.0722_f32.mul_add(blue, 0.2126_f32.mul_add(red, 0.7152 * green)).clamp(0.0, 1.0);
+
+    // The largest interpolation factor towards the original color that keeps the channel within bounds.
+    let limit = |channel: f32| {
+        if channel > 1.0 {
+            (1.0 - gray) / (channel - gray)
+        } else if channel < 0.0 {
+            gray / (gray - channel)
+        } else {
+            1.0
+        }
+    };
+    let factor = limit(red).min(limit(green)).min(limit(blue)).clamp(0.0, 1.0);
+    let scale = |channel: f32| factor.mul_add(channel - gray, gray).clamp(0.0, 1.0);
+
+    Srgba::new(scale(red), scale(green), scale(blue), alpha)
 }
 
+/// An 8-bit image buffer with the given pixel type.
+type Buffer<P> = ImageBuffer<P, Vec<u8>>;
+
+/// A pixel whose channels begin with red, green, and blue, optionally followed by alpha.
+trait RgbPixel: Pixel<Subpixel = u8> + 'static {}
+
+impl RgbPixel for Rgb<u8> {}
+
+impl RgbPixel for Rgba<u8> {}
+
 /// Iterates over each pixel within an image, applying the given closure to its HSVA value.
 ///
 /// # Errors
 ///
 /// This function may return an error if the given closure returns an error.
-fn walk_pixels(
-    image: &mut RgbaImage,
-    mut f: impl FnMut(&mut Hsva<palette::encoding::Srgb>) -> Result<()>,
-) -> Result<()> {
-    for pixel in image.pixels_mut() {
+fn walk_pixels<P: RgbPixel, E>(
+    image: &mut Buffer<P>,
+    codec: Codec,
+    mut f: impl FnMut(&mut Hsva<palette::encoding::Srgb>) -> Result<(), E>,
+) -> Result<(), E> {
+    let width = image.width() as usize;
+    let mut diffusion = codec.dither.then(|| ErrorDiffusion::new(width));
+
+    for (index, pixel) in image.pixels_mut().enumerate() {
         // black magic
         let mut hsva = Hsva::from_color(Srgba::from_components(pixel.to_rgba().0.into()).into_format());
 
         f(&mut hsva)?;
 
-        let rgba: Srgba<f32> = hsva.into_color();
+        let (red, green, blue, alpha) = codec.map_gamut(hsva.into_color_unclamped()).into_components();
+        let rgba = diffusion.as_mut().map_or_else(
+            || [red, green, blue, alpha].map(|c| codec.quantize(c)),
+            |diffusion| {
+                let [red, green, blue] = diffusion.quantize(codec, index % width, [red, green, blue]);
+
+                [red, green, blue, codec.quantize(alpha)]
+            },
+        );
+        let channels = pixel.channels_mut();
 
-        pixel.0 = rgba.into_format().into_components().into();
+        // Pixels without an alpha channel simply drop it.
+        channels.copy_from_slice(&rgba[.. channels.len()]);
     }
 
     Ok(())
 }
 
+/// Iterates over each pixel within an image, applying the given infallible closure to its HSVA value.
+fn map_pixels<P: RgbPixel>(image: &mut Buffer<P>, codec: Codec, mut f: impl FnMut(&mut Hsva<palette::encoding::Srgb>)) {
+    let Ok(()) = self::walk_pixels::<P, Infallible>(image, codec, |hsva| {
+        f(hsva);
+
+        Ok(())
+    });
+}
+
 /// Applies transformations to an image to convert it into a 'dyed' variant.
 ///
 /// # Errors
 ///
-/// This function may return an error if a given filter has an invalid target/operator combination.
+/// This function may return an error if the image is empty or if a given filter has an invalid target/operator
+/// combination.
 pub fn transform_image(config: &DyeColorConfig, image: &mut RgbaImage) -> Result<()> {
-    let target = Hsv::from_color(Srgb::from_components(config.rgb.into()).into_format());
+    self::transform_image_with_target(config, config.target_hsv(), image)
+}
 
-    self::walk_pixels(image, |hsva| {
-        hsva.set_hue(target.hue);
+/// Applies transformations to an image to convert it into a 'dyed' variant, using an already-converted target color.
+///
+/// This allows the target color to be computed once and reused when transforming many images with the same
+/// configuration. The given target should be equivalent to the configuration's `rgb` value.
+///
+/// # Errors
+///
+/// This function may return an error if the image is empty or if a given filter has an invalid target/operator
+/// combination.
+pub fn transform_image_with_target(
+    config: &DyeColorConfig,
+    target: Hsv<palette::encoding::Srgb>,
+    image: &mut RgbaImage,
+) -> Result<()> {
+    self::transform_buffer(config, target, image, &mut Buffers::default())
+}
 
-        for filter in config.filters.iter().filter(|f| f.kind == FilterType::Pixel).copied() {
-            self::apply_pixel_filter(filter, hsva)?;
-        }
+/// Reusable memory for transforming many images, avoiding allocating intermediate images for each one.
+///
+/// A scratch buffer may be shared between any configurations and image sizes, though it is most effective when every
+/// image has the same dimensions.
+#[derive(Clone, Debug, Default)]
+pub struct Scratch {
+    /// The intermediate images of RGBA transformations.
+    buffers: Buffers<Rgba<u8>>,
+}
 
-        Ok(())
-    })?;
+impl Scratch {
+    /// Creates a new, empty scratch buffer.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Applies transformations to an image to convert it into a 'dyed' variant, reusing the given scratch buffer for any
+/// intermediate images.
+///
+/// This behaves identically to [`transform_image`], and the contents of the scratch buffer never affect the result.
+///
+/// # Errors
+///
+/// This function may return an error if the image is empty or if a given filter has an invalid target/operator
+/// combination.
+pub fn transform_image_with_scratch(
+    config: &DyeColorConfig,
+    image: &mut RgbaImage,
+    scratch: &mut Scratch,
+) -> Result<()> {
+    self::transform_buffer(config, config.target_hsv(), image, &mut scratch.buffers)
+}
+
+/// The intermediate images used while transforming an image, which are only reallocated if their sizes change.
+#[derive(Clone, Debug)]
+struct Buffers<P: RgbPixel> {
+    /// A copy of the image before it was transformed, used to restore locked components.
+    original: Buffer<P>,
+    /// A copy of the area of the image that image filters are limited to.
+    bounded: Buffer<P>,
+    /// The intermediate pixels used when blurring the image.
+    blur: BlurBuffers,
+}
 
-    for filter in config.filters.iter().filter(|f| f.kind == FilterType::Image).copied() {
-        self::apply_image_filter(filter, image)?;
+impl<P: RgbPixel> Default for Buffers<P> {
+    fn default() -> Self {
+        Self { original: Buffer::new(0, 0), bounded: Buffer::new(0, 0), blur: BlurBuffers::default() }
     }
+}
 
-    Ok(())
+/// The intermediate pixels used when blurring an image.
+#[derive(Clone, Debug, Default)]
+struct BlurBuffers {
+    /// The weight of each sample of the Gaussian kernel.
+    kernel: Vec<f32>,
+    /// The premultiplied pixels of the image, which also hold the blurred result.
+    pixels: Vec<[f32; 4]>,
+    /// The pixels of the image once blurred horizontally.
+    horizontal: Vec<[f32; 4]>,
 }
 
-/// Applies pixel-specific filters.
+/// Converts a base image into a 'dyed' variant for every configured color, in the order that they are declared within
+/// [`DyeColor`].
+///
+/// The base image is left as-is, and each variant is transformed from its own copy.
 ///
 /// # Errors
 ///
-/// This function may return an error if a given filter has an invalid target/operator combination.
-pub fn apply_pixel_filter(filter: Filter, hsva: &mut Hsva<palette::encoding::Srgb>) -> Result<()> {
-    match filter.target {
-        FilterTarget::Contrast => return Err(Error::invalid_filter(filter)),
-        FilterTarget::Hue => match filter.operation {
-            FilterOperation::Add => hsva.shift_hue_assign(filter.value),
-            FilterOperation::Multiply => hsva.set_hue(hsva.get_hue().into_inner() * filter.value),
-            FilterOperation::Set => hsva.set_hue(filter.value),
-        },
-        FilterTarget::Saturation => match filter.operation {
-            FilterOperation::Add => hsva.saturate_fixed_assign(filter.value),
-            FilterOperation::Multiply => hsva.saturate_assign(filter.value),
-            FilterOperation::Set => hsva.saturation = filter.value.clamp(0.0, 1.0),
-        },
-        FilterTarget::Brightness => match filter.operation {
-            FilterOperation::Add => hsva.value = (hsva.value + filter.value).clamp(0.0, 1.0),
-            FilterOperation::Multiply => hsva.value = (hsva.value * filter.value).clamp(0.0, 1.0),
-            FilterOperation::Set => hsva.value = filter.value.clamp(0.0, 1.0),
-        },
-    };
+/// This function may return an error if the image is empty or if any color's filters have an invalid target/operator
+/// combination.
+pub fn transform_all(config: &Config, base: &RgbaImage) -> Result<Vec<(DyeColor, RgbaImage)>> {
+    let mut scratch = Scratch::new();
+
+    config
+        .sorted_colors()
+        .into_iter()
+        .map(|(color, config)| {
+            let mut image = base.clone();
+
+            self::transform_image_with_scratch(config, &mut image, &mut scratch)?;
+
+            Ok((color, image))
+        })
+        .collect()
+}
+
+/// Applies transformations to an image to convert it into a 'dyed' variant, limited by the given mask.
+///
+/// The mask's luminance at each pixel determines how much of the transformed result is used, where white is fully
+/// transformed and black is left as-is.
+///
+/// # Errors
+///
+/// This function may return an error if the mask's dimensions do not match the image's, or if a given filter has an
+/// invalid target/operator combination.
+pub fn transform_image_masked(config: &DyeColorConfig, image: &mut RgbaImage, mask: &GrayImage) -> Result<()> {
+    if mask.dimensions() != image.dimensions() {
+        return Err(Error::MaskDimensions(mask.dimensions(), image.dimensions()));
+    }
+
+    let mut output = RgbaImage::new(image.width(), image.height());
+
+    self::transform_pixels_into(config, image, &mut output)?;
+    self::blend_masked(&mut output, image, mask)?;
+
+    *image = output;
 
     Ok(())
 }
 
-/// Applies image-specific filters.
+/// Applies transformations to a source image to convert it into a 'dyed' variant, writing the result into the given
+/// destination image.
+///
+/// The source image is left as-is, and every pixel of the destination image is overwritten.
 ///
 /// # Errors
 ///
-/// This function may return an error if a given filter has an invalid target/operator combination.
+/// This function may return an error if the destination's dimensions do not match the source's, if the image is empty,
+/// or if a given filter has an invalid target/operator combination.
+pub fn transform_pixels_into(config: &DyeColorConfig, source: &RgbaImage, destination: &mut RgbaImage) -> Result<()> {
+    if destination.dimensions() != source.dimensions() {
+        return Err(Error::DestinationDimensions(destination.dimensions(), source.dimensions()));
+    }
+
+    destination.copy_from_slice(source);
+
+    self::transform_image(config, destination)
+}
+
+/// Blends a transformed image with its original according to the given mask.
+///
+/// The mask's luminance at each pixel determines how much of the transformed image is kept, where white keeps the
+/// transformed pixel and black restores the original pixel.
+///
+/// # Errors
+///
+/// This function will return an error if the dimensions of the original image or mask do not match the image's.
 #[allow(clippy::cast_possible_truncation)]
-pub fn apply_image_filter(filter: Filter, image: &mut RgbaImage) -> Result<()> {
-    match filter.target {
-        FilterTarget::Contrast => match filter.operation {
-            FilterOperation::Add => contrast_in_place(image, filter.value),
-            FilterOperation::Multiply => contrast_in_place(image, filter.value - 1.0),
-            FilterOperation::Set => return Err(Error::invalid_filter(filter)),
-        },
-        FilterTarget::Hue => match filter.operation {
-            FilterOperation::Add => huerotate_in_place(image, filter.value.round() as i32),
-            FilterOperation::Multiply | FilterOperation::Set => return Err(Error::invalid_filter(filter)),
-        },
-        FilterTarget::Saturation => self::walk_pixels(image, |hsva| self::apply_pixel_filter(filter, hsva))?,
-        FilterTarget::Brightness => match filter.operation {
-            FilterOperation::Add => brighten_in_place(image, filter.value.round() as i32),
-            FilterOperation::Multiply | FilterOperation::Set => return Err(Error::invalid_filter(filter)),
-        },
+pub fn blend_masked(image: &mut RgbaImage, original: &RgbaImage, mask: &GrayImage) -> Result<()> {
+    if mask.dimensions() != image.dimensions() {
+        return Err(Error::MaskDimensions(mask.dimensions(), image.dimensions()));
+    }
+
+    if original.dimensions() != image.dimensions() {
+        return Err(Error::OriginalDimensions(original.dimensions(), image.dimensions()));
+    }
+
+    for ((pixel, original), weight) in image.pixels_mut().zip(original.pixels()).zip(mask.pixels()) {
+        let weight = u16::from(weight.0[0]);
+
+        for (channel, original) in pixel.0.iter_mut().zip(original.0) {
+            // The result is a weighted average of two `u8` values, so it can never truncate.
+            let blended = (u16::from(*channel) * weight) + (u16::from(original) * (255 - weight)) + 127;
+
+            *channel = (blended / 255) as u8;
+        }
+    }
+
+    Ok(())
+}
+
+/// Applies transformations to an image without an alpha channel to convert it into a 'dyed' variant.
+///
+/// This behaves identically to [`transform_image`], but avoids converting the image to and from RGBA.
+///
+/// # Errors
+///
+/// This function may return an error if the image is empty or if a given filter has an invalid target/operator
+/// combination.
+pub fn transform_rgb_image(config: &DyeColorConfig, image: &mut RgbImage) -> Result<()> {
+    self::transform_buffer(config, config.target_hsv(), image, &mut Buffers::default())
+}
+
+/// Applies transformations to an image buffer to convert it into a 'dyed' variant.
+///
+/// The given buffers are overwritten with any intermediate images, and are only reallocated if their sizes differ.
+///
+/// # Errors
+///
+/// This function may return an error if the image is empty or if a given filter has an invalid target/operator
+/// combination.
+fn transform_buffer<P: RgbPixel>(
+    config: &DyeColorConfig,
+    target: Hsv<palette::encoding::Srgb>,
+    image: &mut Buffer<P>,
+    buffers: &mut Buffers<P>,
+) -> Result<()> {
+    self::check_dimensions(image.dimensions())?;
+
+    let codec = Codec::of(config);
+    let Buffers { original, bounded, blur } = buffers;
+
+    if let (true, Some(key)) = (config.uses_chroma_key(), config.chroma_key) {
+        self::apply_chroma_key(image, key, config.chroma_key_tolerance, config.distance_metric);
+    }
+
+    let keeps_original = !config.lock.is_empty() || config.preserve_transparent_rgb;
+
+    if keeps_original && original.dimensions() == image.dimensions() {
+        original.copy_from_slice(image);
+    } else if keeps_original {
+        original.clone_from(image);
+    }
+
+    let original = keeps_original.then_some(&*original);
+    let filters = config.active_filters();
+
+    match config.filter_stage {
+        FilterStage::AfterHue => {
+            // The hue is replaced within the same pass as the pixel filters to avoid quantizing each pixel twice.
+            self::walk_pixels(image, codec, |hsva| {
+                self::replace_hue(config, target, hsva);
+                self::apply_pixel_filters(filters, hsva)
+            })?;
+
+            self::within_opaque_bounds(config, image, bounded, |image| {
+                self::apply_image_filters(filters, codec, image, blur)
+            })?;
+        }
+        FilterStage::BeforeHue => {
+            if filters.iter().any(|f| f.kind == FilterType::Pixel) {
+                self::walk_pixels(image, codec, |hsva| self::apply_pixel_filters(filters, hsva))?;
+            }
+
+            self::within_opaque_bounds(config, image, bounded, |image| {
+                self::apply_image_filters(filters, codec, image, blur)
+            })?;
+
+            self::map_pixels(image, codec, |hsva| self::replace_hue(config, target, hsva));
+        }
+    }
+
+    if let Some(original) = original {
+        self::restore_original(config, original, codec, image);
+    }
+
+    if config.allow_alpha {
+        for alpha in image.pixels_mut().filter_map(|pixel| pixel.channels_mut().get_mut(3)) {
+            *alpha = self::scale_alpha(*alpha, config.alpha_scale);
+        }
+    }
+
+    Ok(())
+}
+
+/// Calls the given function with the image, or with only the bounding box of its non-transparent pixels if the
+/// configuration requests it.
+///
+/// When scoped, the function is given a copy of the bounded area that is written back afterwards, and it is not called
+/// at all if the image is fully transparent. The copy is stored within the given buffer, which is only reallocated if
+/// its dimensions differ.
+///
+/// # Errors
+///
+/// This function may return an error if the given function returns an error.
+fn within_opaque_bounds<P: RgbPixel>(
+    config: &DyeColorConfig,
+    image: &mut Buffer<P>,
+    bounded: &mut Buffer<P>,
+    f: impl FnOnce(&mut Buffer<P>) -> Result<()>,
+) -> Result<()> {
+    if !config.opaque_bounds_only {
+        return f(image);
+    }
+
+    let Some(Rect { x, y, width, height }) = self::opaque_bounds_of(image) else {
+        return Ok(());
     };
 
+    if bounded.dimensions() != (width, height) {
+        *bounded = Buffer::new(width, height);
+    }
+
+    bounded.copy_from(&*image.view(x, y, width, height), 0, 0)?;
+
+    f(bounded)?;
+
+    Ok(image.copy_from(bounded, x, y)?)
+}
+
+/// Returns the smallest rectangle containing every non-transparent pixel within the given image.
+///
+/// This returns `None` if the image has no non-transparent pixels.
+#[must_use]
+pub fn opaque_bounds(image: &RgbaImage) -> Option<Rect> {
+    self::opaque_bounds_of(image)
+}
+
+/// Returns the smallest rectangle containing every non-transparent pixel within the given image buffer.
+///
+/// Pixels without an alpha channel are always considered opaque.
+fn opaque_bounds_of<P: RgbPixel>(image: &Buffer<P>) -> Option<Rect> {
+    let mut bounds: Option<(u32, u32, u32, u32)> = None;
+
+    for (x, y, pixel) in image.enumerate_pixels() {
+        if pixel.channels().get(3).is_some_and(|alpha| *alpha == 0) {
+            continue;
+        }
+
+        bounds =
+            Some(bounds.map_or((x, y, x, y), |(left, top, right, bottom)| {
+                (left.min(x), top.min(y), right.max(x), bottom.max(y))
+            }));
+    }
+
+    bounds.map(|(left, top, right, bottom)| Rect { x: left, y: top, width: right - left + 1, height: bottom - top + 1 })
+}
+
+/// Applies transformations to an image like [`transform_image`], calling the given function with the image after each
+/// stage of the transformation.
+///
+/// The function is given the filter that was just applied, or `None` for the hue replacement. With
+/// [`FilterStage::AfterHue`], the hue is replaced first and each filter is applied afterwards, while with
+/// [`FilterStage::BeforeHue`], each filter is applied first and the hue is replaced last. Pixel filters are always
+/// applied before image filters, and any locked components are restored after the final stage.
+///
+/// Since the image is quantized after every stage, the final result may differ slightly from that of
+/// [`transform_image`].
+///
+/// # Errors
+///
+/// This function may return an error if the image is empty, if a given filter has an invalid target/operator
+/// combination, or if the given function returns an error.
+pub fn transform_image_staged(
+    config: &DyeColorConfig,
+    image: &mut RgbaImage,
+    mut stage: impl FnMut(Option<&Filter>, &RgbaImage) -> Result<()>,
+) -> Result<()> {
+    self::check_dimensions(image.dimensions())?;
+
+    let codec = Codec::of(config);
+    let target = config.target_hsv();
+
+    if let (true, Some(key)) = (config.uses_chroma_key(), config.chroma_key) {
+        self::apply_chroma_key(image, key, config.chroma_key_tolerance, config.distance_metric);
+    }
+
+    let original = (!config.lock.is_empty() || config.preserve_transparent_rgb).then(|| image.clone());
+    let before_hue = config.filter_stage == FilterStage::BeforeHue;
+    let Buffers { mut bounded, mut blur, .. } = Buffers::default();
+
+    if !before_hue {
+        self::map_pixels(image, codec, |hsva| self::replace_hue(config, target, hsva));
+
+        stage(None, image)?;
+    }
+
+    let mut filters = config.active_filters().to_vec();
+
+    config::sort_filters(&mut filters);
+
+    for filter in &filters {
+        match filter.kind {
+            FilterType::Pixel => self::walk_pixels(image, codec, |hsva| self::apply_pixel_filter(*filter, hsva))?,
+            FilterType::Image => self::within_opaque_bounds(config, image, &mut bounded, |image| {
+                self::apply_image_filter_with(*filter, codec, image, &mut blur)
+            })?,
+        }
+
+        stage(Some(filter), image)?;
+    }
+
+    if before_hue {
+        self::map_pixels(image, codec, |hsva| self::replace_hue(config, target, hsva));
+
+        stage(None, image)?;
+    }
+
+    if let Some(original) = original {
+        self::restore_original(config, &original, codec, image);
+    }
+
+    if config.allow_alpha {
+        for alpha in image.pixels_mut().filter_map(|pixel| pixel.channels_mut().get_mut(3)) {
+            *alpha = self::scale_alpha(*alpha, config.alpha_scale);
+        }
+    }
+
     Ok(())
 }
+
+/// Replaces the hue of the given pixel with the target hue, or blends it towards the color in RGB space if configured.
+///
+/// If the configuration has a color map, it is used instead.
+fn replace_hue(
+    config: &DyeColorConfig,
+    target: Hsv<palette::encoding::Srgb>,
+    hsva: &mut Hsva<palette::encoding::Srgb>,
+) {
+    if !config.color_map.is_empty() {
+        return self::apply_color_map(&config.color_map, config.distance_metric, hsva);
+    }
+
+    if let Some(factor) = config.rgb_blend {
+        self::blend_rgb(hsva, config.rgb, factor);
+    } else {
+        hsva.set_hue(target.hue);
+        hsva.saturation = hsva.saturation.max(config.min_saturation);
+    }
+}
+
+/// Replaces the given pixel with the target of the nearest source color within its tolerance, keeping its alpha.
+///
+/// The pixel is left as-is if it is not within the tolerance of any source color.
+fn apply_color_map(color_map: &[ColorMapping], metric: DistanceMetric, hsva: &mut Hsva<palette::encoding::Srgb>) {
+    let rgb: Srgb = Srgb::from_color(hsva.color);
+    let distance = |from: [u8; 3]| self::color_distance(metric, rgb, Srgb::from_components(from.into()).into_format());
+
+    let nearest = color_map
+        .iter()
+        .map(|mapping| (mapping, distance(mapping.from)))
+        .filter(|(mapping, distance)| *distance <= mapping.tolerance)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b));
+
+    if let Some((mapping, _)) = nearest {
+        let to: Srgb = Srgb::from_components(mapping.to.into()).into_format();
+
+        hsva.color = Hsv::from_color(to);
+    }
+}
+
+/// Returns the distance between two colors using the given metric.
+///
+/// RGB-based metrics are measured out of 255 for each channel, while Lab-based metrics are measured in Delta E.
+fn color_distance(metric: DistanceMetric, a: Srgb, b: Srgb) -> f32 {
+    let (red, green, blue) = ((a.red - b.red) * 255.0, (a.green - b.green) * 255.0, (a.blue - b.blue) * 255.0);
+
+    match metric {
+        DistanceMetric::Euclidean => blue.mul_add(blue, red.mul_add(red, green * green)).sqrt(),
+        // The 'redmean' approximation, which weights each channel by how sensitive the eye is to it.
+        DistanceMetric::Weighted => {
+            let mean = (a.red + b.red) * 255.0 / 2.0;
+            let (red_weight, blue_weight) = (2.0 + (mean / 256.0), 2.0 + ((255.0 - mean) / 256.0));
+
+            (blue_weight * blue).mul_add(blue, (4.0 * green).mul_add(green, red_weight * red * red)).sqrt()
+        }
+        DistanceMetric::Cie76 => {
+            let (a, b): (Lab, Lab) = (Lab::from_color(a), Lab::from_color(b));
+
+            a.delta_e(b)
+        }
+        DistanceMetric::Ciede2000 => {
+            let (a, b): (Lab, Lab) = (Lab::from_color(a), Lab::from_color(b));
+
+            a.difference(b)
+        }
+    }
+}
+
+/// Scales the given alpha channel by the given factor, where the result is clamped into the channel's range.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn scale_alpha(alpha: u8, scale: f32) -> u8 {
+    (f32::from(alpha) * scale).round().clamp(0.0, 255.0) as u8
+}
+
+/// Ensures that an image with the given dimensions contains at least one pixel.
+///
+/// Single-pixel images are valid, as every filter only considers pixels individually or handles missing neighbors.
+///
+/// # Errors
+///
+/// This function will return an error if either dimension is zero.
+const fn check_dimensions((width, height): (u32, u32)) -> Result<()> {
+    if width == 0 || height == 0 {
+        return Err(Error::EmptyImage(width, height));
+    }
+
+    Ok(())
+}
+
+/// Linearly blends the given pixel towards the given RGB components in RGB space, keeping its alpha.
+///
+/// A factor of 0.0 leaves the pixel unchanged, while a factor of 1.0 replaces it with the given color.
+fn blend_rgb(hsva: &mut Hsva<palette::encoding::Srgb>, rgb: [u8; 3], factor: f32) {
+    let target: Srgb = Srgb::from_components(rgb.into()).into_format();
+    let mut rgba = Srgba::from_color(*hsva);
+
+    rgba.red += (target.red - rgba.red) * factor;
+    rgba.green += (target.green - rgba.green) * factor;
+    rgba.blue += (target.blue - rgba.blue) * factor;
+
+    *hsva = Hsva::from_color(rgba);
+}
+
+/// Makes every pixel within the given tolerance of the key color fully transparent.
+///
+/// The tolerance is the largest allowed distance from the key color, measured using the given metric. Pixels without an
+/// alpha channel are left as-is.
+fn apply_chroma_key<P: RgbPixel>(image: &mut Buffer<P>, key: [u8; 3], tolerance: u8, metric: DistanceMetric) {
+    let key: Srgb = Srgb::from_components(key.into()).into_format();
+
+    for pixel in image.pixels_mut() {
+        let channels = pixel.channels_mut();
+        let rgb: Srgb = Srgb::new(channels[0], channels[1], channels[2]).into_format();
+        let matches = self::color_distance(metric, rgb, key) <= f32::from(tolerance);
+
+        if let (true, Some(alpha)) = (matches, channels.get_mut(3)) {
+            *alpha = 0;
+        }
+    }
+}
+
+/// Restores any locked components, and the RGB components of fully transparent pixels if configured, from the
+/// original image.
+fn restore_original<P: RgbPixel>(config: &DyeColorConfig, original: &Buffer<P>, codec: Codec, image: &mut Buffer<P>) {
+    if !config.lock.is_empty() {
+        self::restore_locked(&config.lock, original, codec, image);
+    }
+
+    if config.preserve_transparent_rgb {
+        for (pixel, original) in image.pixels_mut().zip(original.pixels()) {
+            // Pixels without an alpha channel are never transparent.
+            if pixel.channels().get(3) == Some(&0) {
+                pixel.channels_mut()[.. 3].copy_from_slice(&original.channels()[.. 3]);
+            }
+        }
+    }
+}
+
+/// Restores the locked components of each pixel within an image to their values within the original image.
+fn restore_locked<P: RgbPixel>(lock: &[FilterTarget], original: &Buffer<P>, codec: Codec, image: &mut Buffer<P>) {
+    let mut originals = original.pixels();
+
+    self::map_pixels(image, codec, |hsva| {
+        let Some(pixel) = originals.next() else { return };
+        let source: Hsva<palette::encoding::Srgb> =
+            Hsva::from_color(Srgba::from_components(pixel.to_rgba().0.into()).into_format());
+
+        for target in lock {
+            match target {
+                FilterTarget::Hue => hsva.hue = source.hue,
+                FilterTarget::Saturation => hsva.saturation = source.saturation,
+                FilterTarget::Brightness => hsva.value = source.value,
+                FilterTarget::Contrast
+                | FilterTarget::Noise
+                | FilterTarget::SelectiveHue
+                | FilterTarget::Quantize
+                | FilterTarget::Blur
+                | FilterTarget::Vignette => {}
+            }
+        }
+    });
+}
+
+/// Applies a color's brightness and contrast filters to a grayscale image, keeping it in its native format.
+///
+/// Since grayscale pixels have no hue, the color's hue is not applied, meaning that the color's filter stage has no
+/// effect. Filters and the alpha scale are otherwise applied in the same order as [`transform_image`].
+///
+/// # Errors
+///
+/// This function may return an error if the image is empty, if the configuration does not preserve grayscale (see
+/// [`DyeColorConfig::preserves_grayscale`]), if the configuration uses an option that is only supported for RGB images
+/// (see [`DyeColorConfig::rgb_only_option`]), or if a given filter has an invalid target/operator combination.
+#[allow(clippy::cast_possible_truncation)]
+pub fn transform_gray_image(config: &DyeColorConfig, image: &mut GrayAlphaImage) -> Result<()> {
+    self::check_dimensions(image.dimensions())?;
+
+    if let Some(option) = config.rgb_only_option() {
+        return Err(Error::UnsupportedGrayOption(option));
+    }
+
+    if !config.preserves_grayscale() {
+        return Err(Error::RecolorsGrayscale);
+    }
+
+    let codec = Codec::of(config);
+
+    if config.active_filters().iter().any(|f| f.kind == FilterType::Pixel) {
+        for pixel in image.pixels_mut() {
+            let [luma, alpha] = pixel.0;
+            let mut hsva = Hsva::new(0.0, 0.0, f32::from(luma) / 255.0, f32::from(alpha) / 255.0);
+
+            self::apply_pixel_filters(config.active_filters(), &mut hsva)?;
+
+            pixel.0 = [codec.quantize(hsva.value), alpha];
+        }
+    }
+
+    for filter in config.active_filters().iter().filter(|f| f.kind == FilterType::Image).copied() {
+        for _ in 0 .. filter.passes {
+            match (filter.target, filter.operation) {
+                (FilterTarget::Contrast, FilterOperation::Add) => contrast_in_place(image, filter.value),
+                (FilterTarget::Contrast, FilterOperation::Multiply) => contrast_in_place(image, filter.value - 1.0),
+                (FilterTarget::Brightness, FilterOperation::Add) => {
+                    brighten_in_place(image, filter.value.round() as i32);
+                }
+                _ => return Err(Error::invalid_filter(filter)),
+            }
+        }
+    }
+
+    if config.allow_alpha {
+        for pixel in image.pixels_mut() {
+            pixel.0[1] = self::scale_alpha(pixel.0[1], config.alpha_scale);
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns a single image containing each of the given images laid out in a grid, reading left-to-right then
+/// top-to-bottom.
+///
+/// Every cell is as large as the largest image, with smaller images centered horizontally. If labeled, each cell also
+/// has an opaque strip beneath its image containing the color's name, and cells are widened to fit the longest name.
+#[must_use]
+pub fn contact_sheet(tiles: &[(DyeColor, RgbaImage)], columns: u32, labeled: bool) -> RgbaImage {
+    let count = u32::try_from(tiles.len()).unwrap_or(u32::MAX);
+    let columns = columns.clamp(1, count.max(1));
+    let rows = count.div_ceil(columns);
+
+    let label_width = tiles.iter().map(|(color, _)| font::text_width(&color.to_string())).max().unwrap_or(0);
+    let tile_width = tiles.iter().map(|(_, tile)| tile.width()).max().unwrap_or(0);
+    let tile_height = tiles.iter().map(|(_, tile)| tile.height()).max().unwrap_or(0);
+    let (cell_width, cell_height) = if labeled {
+        (tile_width.max(label_width + 2), tile_height + font::LINE_HEIGHT)
+    } else {
+        (tile_width, tile_height)
+    };
+
+    let mut sheet = RgbaImage::new(cell_width.saturating_mul(columns), cell_height.saturating_mul(rows));
+
+    for (index, (color, tile)) in (0_u32 ..).zip(tiles) {
+        let (x, y) = ((index % columns) * cell_width, (index / columns) * cell_height);
+
+        image::imageops::replace(&mut sheet, tile, i64::from(x + ((cell_width - tile.width()) / 2)), i64::from(y));
+
+        if !labeled {
+            continue;
+        }
+
+        let label = color.to_string();
+        let strip = RgbaImage::from_pixel(cell_width, font::LINE_HEIGHT, Rgba([u8::MAX; 4]));
+
+        image::imageops::replace(&mut sheet, &strip, i64::from(x), i64::from(y + tile_height));
+
+        let position = (x + ((cell_width - font::text_width(&label)) / 2), y + tile_height + 1);
+
+        font::draw_text(&mut sheet, &label, position, Rgba([0, 0, 0, u8::MAX]));
+    }
+
+    sheet
+}
+
+/// Returns a copy of the given image surrounded by an opaque border of the given color and width.
+#[must_use]
+pub fn add_border(image: &RgbaImage, width: u32, rgb: [u8; 3]) -> RgbaImage {
+    let [red, green, blue] = rgb;
+    let mut output = RgbaImage::from_pixel(
+        image.width().saturating_add(width.saturating_mul(2)),
+        image.height().saturating_add(width.saturating_mul(2)),
+        Rgba([red, green, blue, u8::MAX]),
+    );
+
+    image::imageops::replace(&mut output, image, i64::from(width), i64::from(width));
+
+    output
+}
+
+/// Samples the RGB value of every dye color from an image of swatches laid out in a grid.
+///
+/// Each swatch is sampled at the center of its cell, and cells are assigned to colors in declaration order, reading
+/// left-to-right then top-to-bottom.
+///
+/// # Errors
+///
+/// This function will return an error if the grid does not have exactly one cell per dye color, or if the image is too
+/// small for the given grid.
+pub fn sample_palette(image: &RgbaImage, columns: u32, rows: u32) -> Result<BTreeMap<DyeColor, [u8; 3]>> {
+    let cell_width = image.width().checked_div(columns).unwrap_or(0);
+    let cell_height = image.height().checked_div(rows).unwrap_or(0);
+
+    if u64::from(columns) * u64::from(rows) != DyeColor::ALL.len() as u64 || cell_width == 0 || cell_height == 0 {
+        return Err(Error::InvalidPaletteLayout(columns, rows));
+    }
+
+    let cells = (0 .. rows).flat_map(|row| (0 .. columns).map(move |column| (column, row)));
+
+    Ok(DyeColor::ALL
+        .into_iter()
+        .zip(cells)
+        .map(|(color, (column, row))| {
+            let x = (column * cell_width) + (cell_width / 2);
+            let y = (row * cell_height) + (cell_height / 2);
+            let [red, green, blue, _] = image.get_pixel(x, y).0;
+
+            (color, [red, green, blue])
+        })
+        .collect())
+}
+
+/// Returns every mipmap level of the given image, starting with the level at half of its size and ending with the level
+/// that is a single pixel in size.
+///
+/// Each level halves the previous level's dimensions, rounding down but never below one pixel, by averaging each 2x2
+/// block of pixels. Colors are weighted by their alpha, so transparent pixels never darken their neighbors. Images with
+/// no width or no height have no levels.
+#[must_use]
+pub fn mipmaps(image: &RgbaImage) -> Vec<RgbaImage> {
+    let mut levels: Vec<RgbaImage> = Vec::new();
+
+    if image.width() == 0 || image.height() == 0 {
+        return levels;
+    }
+
+    loop {
+        let current = levels.last().unwrap_or(image);
+
+        if current.width() <= 1 && current.height() <= 1 {
+            return levels;
+        }
+
+        let next = self::halve(current);
+
+        levels.push(next);
+    }
+}
+
+/// Halves the dimensions of the given image by averaging each 2x2 block of pixels, weighted by their alpha.
+///
+/// Each dimension is never reduced below one pixel, and blocks along the edges of odd dimensions are clipped.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn halve(image: &RgbaImage) -> RgbaImage {
+    let (width, height) = ((image.width() / 2).max(1), (image.height() / 2).max(1));
+
+    RgbaImage::from_fn(width, height, |x, y| {
+        let mut sums = [0.0_f32; 4];
+
+        for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            let (x, y) = ((x * 2 + dx).min(image.width() - 1), (y * 2 + dy).min(image.height() - 1));
+            let [red, green, blue, alpha] = image.get_pixel(x, y).0.map(f32::from);
+
+            sums[0] = red.mul_add(alpha, sums[0]);
+            sums[1] = green.mul_add(alpha, sums[1]);
+            sums[2] = blue.mul_add(alpha, sums[2]);
+            sums[3] += alpha;
+        }
+
+        let [red, green, blue, alpha] = sums;
+        let rgb = if alpha > 0.0 { [red / alpha, green / alpha, blue / alpha] } else { [0.0; 3] };
+        let [red, green, blue] = rgb;
+
+        Rgba([red, green, blue, alpha / 4.0].map(|c| c.round().clamp(0.0, 255.0) as u8))
+    })
+}
+
+/// Returns a grayscale image containing the alpha channel of the given image.
+#[must_use]
+pub fn alpha_mask(image: &RgbaImage) -> GrayImage {
+    GrayImage::from_fn(image.width(), image.height(), |x, y| Luma([image.get_pixel(x, y).0[3]]))
+}
+
+/// Returns the average color of every non-transparent pixel within the given image.
+///
+/// This returns black if the image has no non-transparent pixels.
+#[must_use]
+#[allow(clippy::cast_possible_truncation)]
+pub fn dominant_color(image: &RgbaImage) -> [u8; 3] {
+    let mut sums = [0_u64; 3];
+    let mut count = 0_u64;
+
+    for pixel in image.pixels().filter(|p| p.0[3] > 0) {
+        for (sum, channel) in sums.iter_mut().zip(&pixel.0[.. 3]) {
+            *sum += u64::from(*channel);
+        }
+
+        count += 1;
+    }
+
+    if count == 0 {
+        return [0; 3];
+    }
+
+    // Each average is at most `u8::MAX`, so this can never truncate.
+    sums.map(|sum| ((sum + count / 2) / count) as u8)
+}
+
+/// The largest factor that [`balance_luminance`] will scale an image's brightness by.
+pub const MAX_BALANCE_FACTOR: f32 = 2.0;
+
+/// Returns the mean perceived luminance of every non-transparent pixel within the given image.
+///
+/// Luminance is computed from the gamma-encoded channels, and is within `0.0..=1.0`. This returns `None` if the image
+/// has no non-transparent pixels.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn mean_luminance(image: &RgbaImage) -> Option<f32> {
+    let (sum, count) = image.pixels().filter(|p| p.0[3] > 0).fold((0.0, 0_u64), |(sum, count), pixel| {
+        let [red, green, blue, _] = pixel.0.map(|c| f32::from(c) / 255.0);
+
+        (sum + 0.0722_f32.mul_add(blue, 0.2126_f32.mul_add(red, 0.7152 * green)), count + 1)
+    });
+
+    (count > 0).then(|| sum / count as f32)
+}
+
+/// Scales the brightness of an image so that its mean perceived luminance approaches the given target.
+///
+/// The scaling factor is bounded by [`MAX_BALANCE_FACTOR`] in both directions to avoid washing out or crushing the
+/// image, so the target may not always be reached.
+pub fn balance_luminance(image: &mut RgbaImage, target: f32) {
+    let Some(mean) = self::mean_luminance(image).filter(|m| *m > 0.0) else {
+        return;
+    };
+
+    // Every channel scales linearly with the HSV value, so scaling it scales the luminance by the same factor.
+    let factor = (target / mean).clamp(MAX_BALANCE_FACTOR.recip(), MAX_BALANCE_FACTOR);
+
+    self::map_pixels(image, Codec::default(), |hsva| {
+        hsva.value = (hsva.value * factor).clamp(0.0, 1.0);
+    });
+}
+
+/// Remaps the brightness of an image so that its distribution matches that of the given reference image.
+///
+/// This uses standard histogram matching on each pixel's HSV value, meaning that hue and saturation are left as-is.
+/// Fully transparent pixels are ignored within both images.
+///
+/// This is not available as a [`FilterTarget`], since filters are plain values that are unable to hold a reference
+/// image. It is instead meant to be applied once an image is transformed, as is done by the command-line interface's
+/// `--match-histogram` option.
+#[allow(clippy::cast_possible_truncation)]
+pub fn match_histogram(image: &mut RgbaImage, reference: &RgbaImage) {
+    let (Some(source), Some(target)) = (self::value_cdf(image), self::value_cdf(reference)) else {
+        return;
+    };
+
+    let mut mapping = [u8::MAX; 256];
+
+    for (level, probability) in source.iter().enumerate() {
+        if let Some(matched) = target.iter().position(|p| p >= probability) {
+            mapping[level] = matched as u8;
+        }
+    }
+
+    self::map_pixels(image, Codec::default(), |hsva| {
+        if hsva.alpha > 0.0 {
+            hsva.value = f32::from(mapping[self::value_level(hsva.value)]) / 255.0;
+        }
+    });
+}
+
+/// Returns the 8-bit level of the given HSV value.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn value_level(value: f32) -> usize {
+    (value.clamp(0.0, 1.0) * 255.0).round() as usize
+}
+
+/// Returns the cumulative distribution of each non-transparent pixel's HSV value within the given image.
+///
+/// This returns `None` if the image has no non-transparent pixels.
+#[allow(clippy::cast_precision_loss)]
+fn value_cdf(image: &RgbaImage) -> Option<[f32; 256]> {
+    let mut histogram = [0_u64; 256];
+
+    // A pixel's HSV value is equal to its largest color channel.
+    for [red, green, blue, _] in image.pixels().filter(|p| p.0[3] > 0).map(|p| p.0) {
+        histogram[usize::from(red.max(green).max(blue))] += 1;
+    }
+
+    let total = histogram.iter().sum::<u64>();
+
+    if total == 0 {
+        return None;
+    }
+
+    let mut cdf = [0.0; 256];
+    let mut running = 0;
+
+    for (probability, count) in cdf.iter_mut().zip(histogram) {
+        running += count;
+        *probability = running as f32 / total as f32;
+    }
+
+    Some(cdf)
+}
+
+/// Applies the given filters to an image without replacing its hue.
+///
+/// All pixel filters are applied first, in order, followed by all image filters, in order.
+///
+/// # Errors
+///
+/// This function may return an error if a given filter has an invalid target/operator combination.
+pub fn apply_filters(filters: &[Filter], image: &mut RgbaImage) -> Result<()> {
+    let codec = Codec::default();
+
+    if filters.iter().any(|f| f.kind == FilterType::Pixel) {
+        self::walk_pixels(image, codec, |hsva| self::apply_pixel_filters(filters, hsva))?;
+    }
+
+    self::apply_image_filters(filters, codec, image, &mut BlurBuffers::default())
+}
+
+/// Applies every pixel filter within the given list to a single pixel, in order.
+///
+/// # Errors
+///
+/// This function may return an error if a given filter has an invalid target/operator combination.
+fn apply_pixel_filters(filters: &[Filter], hsva: &mut Hsva<palette::encoding::Srgb>) -> Result<()> {
+    for filter in filters.iter().filter(|f| f.kind == FilterType::Pixel).copied() {
+        self::apply_pixel_filter(filter, hsva)?;
+    }
+
+    Ok(())
+}
+
+/// Applies every image filter within the given list to an image, in order.
+///
+/// # Errors
+///
+/// This function may return an error if a given filter has an invalid target/operator combination.
+fn apply_image_filters<P: RgbPixel>(
+    filters: &[Filter],
+    codec: Codec,
+    image: &mut Buffer<P>,
+    blur: &mut BlurBuffers,
+) -> Result<()> {
+    for filter in filters.iter().filter(|f| f.kind == FilterType::Image).copied() {
+        self::apply_image_filter_with(filter, codec, image, blur)?;
+    }
+
+    Ok(())
+}
+
+/// Applies pixel-specific filters.
+///
+/// If the filter is alpha-weighted, its effect is scaled by the pixel's alpha.
+///
+/// # Errors
+///
+/// This function may return an error if a given filter has an invalid target/operator combination or a non-finite
+/// value.
+pub fn apply_pixel_filter(filter: Filter, hsva: &mut Hsva<palette::encoding::Srgb>) -> Result<()> {
+    if !filter.value.is_finite() {
+        return Err(Error::non_finite_value(filter));
+    }
+
+    if !filter.condition.matches(&hsva.color) {
+        return Ok(());
+    }
+
+    if !filter.alpha_weighted {
+        return self::apply_unweighted_pixel_filter(filter, hsva);
+    }
+
+    let before = *hsva;
+    let weight = before.alpha;
+
+    self::apply_unweighted_pixel_filter(filter, hsva)?;
+
+    // The hue difference is normalized into `-180.0..=180.0`, so this always blends along the shortest arc.
+    let hue = (hsva.hue - before.hue).into_degrees();
+
+    hsva.hue = RgbHue::from_degrees(weight.mul_add(hue, before.hue.into_degrees()));
+    hsva.saturation = weight.mul_add(hsva.saturation - before.saturation, before.saturation);
+    hsva.value = weight.mul_add(hsva.value - before.value, before.value);
+
+    Ok(())
+}
+
+/// Applies pixel-specific filters, ignoring whether they are alpha-weighted.
+///
+/// # Errors
+///
+/// This function may return an error if a given filter has an invalid target/operator combination.
+fn apply_unweighted_pixel_filter(filter: Filter, hsva: &mut Hsva<palette::encoding::Srgb>) -> Result<()> {
+    for _ in 0 .. filter.passes {
+        match filter.target {
+            FilterTarget::Contrast
+            | FilterTarget::Noise
+            | FilterTarget::SelectiveHue
+            | FilterTarget::Quantize
+            | FilterTarget::Blur
+            | FilterTarget::Vignette => return Err(Error::invalid_filter(filter)),
+            FilterTarget::Hue => match filter.operation {
+                FilterOperation::Add => hsva.shift_hue_assign(filter.value),
+                // The hue is normalized into `0.0..360.0` both before and after scaling, since it is circular.
+                FilterOperation::Multiply => {
+                    let degrees = hsva.get_hue().into_positive_degrees() * filter.value;
+
+                    hsva.set_hue(RgbHue::from_degrees(degrees.rem_euclid(360.0)));
+                }
+                FilterOperation::Set => hsva.set_hue(filter.value),
+            },
+            // The saturation is always clamped into `0.0..=1.0` afterwards, so that large or negative values can never
+            // push a pixel out of gamut.
+            FilterTarget::Saturation => {
+                match filter.operation {
+                    FilterOperation::Add => hsva.saturate_fixed_assign(filter.value),
+                    FilterOperation::Multiply => hsva.saturate_assign(filter.value),
+                    FilterOperation::Set => hsva.saturation = filter.value,
+                }
+
+                hsva.saturation = hsva.saturation.clamp(0.0, 1.0);
+            }
+            FilterTarget::Brightness => match filter.operation {
+                FilterOperation::Add => hsva.value = (hsva.value + filter.value).clamp(0.0, 1.0),
+                FilterOperation::Multiply => hsva.value = (hsva.value * filter.value).clamp(0.0, 1.0),
+                FilterOperation::Set => hsva.value = filter.value.clamp(0.0, 1.0),
+            },
+        }
+    }
+
+    Ok(())
+}
+
+/// Applies image-specific filters.
+///
+/// # Errors
+///
+/// This function may return an error if a given filter has an invalid target/operator combination or a non-finite
+/// value.
+pub fn apply_image_filter(filter: Filter, image: &mut RgbaImage) -> Result<()> {
+    self::apply_image_filter_with(filter, Codec::default(), image, &mut BlurBuffers::default())
+}
+
+/// Applies image-specific filters, limited by the given mask.
+///
+/// The filter is applied to the whole image before being composited back using the mask, so filters that consider
+/// neighboring pixels still see the unmasked area. The mask's luminance at each pixel determines how much of the
+/// filtered result is used, where white is fully filtered and black is left as-is.
+///
+/// # Errors
+///
+/// This function may return an error if the mask's dimensions do not match the image's, or if a given filter has an
+/// invalid target/operator combination or a non-finite value.
+pub fn apply_image_filter_masked(filter: Filter, image: &mut RgbaImage, mask: &GrayImage) -> Result<()> {
+    if mask.dimensions() != image.dimensions() {
+        return Err(Error::MaskDimensions(mask.dimensions(), image.dimensions()));
+    }
+
+    let original = image.clone();
+
+    self::apply_image_filter(filter, image)?;
+    self::blend_masked(image, &original, mask)
+}
+
+/// Applies image-specific filters using the given codec, reusing the given buffers when blurring.
+///
+/// # Errors
+///
+/// This function may return an error if a given filter has an invalid target/operator combination or a non-finite
+/// value.
+#[allow(clippy::cast_possible_truncation)]
+fn apply_image_filter_with<P: RgbPixel>(
+    filter: Filter,
+    codec: Codec,
+    image: &mut Buffer<P>,
+    blur: &mut BlurBuffers,
+) -> Result<()> {
+    if !filter.value.is_finite() {
+        return Err(Error::non_finite_value(filter));
+    }
+
+    // Each pass is applied to the whole image, so per-pixel filters must only apply once per pass.
+    let single = Filter { passes: 1, ..filter };
+
+    for _ in 0 .. filter.passes {
+        match filter.target {
+            FilterTarget::Contrast => match filter.operation {
+                FilterOperation::Add => contrast_in_place(image, filter.value),
+                FilterOperation::Multiply => contrast_in_place(image, filter.value - 1.0),
+                FilterOperation::Set => return Err(Error::invalid_filter(filter)),
+            },
+            FilterTarget::Hue => match filter.operation {
+                // The rotation is normalized first so that, for example, `360.0` is an exact no-op and `-30.0` is
+                // exactly equal to `330.0`.
+                FilterOperation::Add => {
+                    let rotation = Filter { value: filter.value.rem_euclid(360.0), ..single };
+
+                    self::walk_pixels(image, codec, |hsva| self::apply_pixel_filter(rotation, hsva))?;
+                }
+                // This is equivalent to the base recolor of `transform_image`, just with an explicit hue.
+                FilterOperation::Set => {
+                    self::walk_pixels(image, codec, |hsva| self::apply_pixel_filter(single, hsva))?;
+                }
+                FilterOperation::Multiply => return Err(Error::invalid_filter(filter)),
+            },
+            FilterTarget::Saturation => self::walk_pixels(image, codec, |hsva| self::apply_pixel_filter(single, hsva))?,
+            FilterTarget::Brightness => match filter.operation {
+                FilterOperation::Add => brighten_in_place(image, filter.value.round() as i32),
+                FilterOperation::Multiply | FilterOperation::Set => return Err(Error::invalid_filter(filter)),
+            },
+            FilterTarget::Noise => match filter.operation {
+                FilterOperation::Add => self::apply_noise(filter.seed, filter.value, codec, image),
+                FilterOperation::Multiply | FilterOperation::Set => return Err(Error::invalid_filter(filter)),
+            },
+            FilterTarget::SelectiveHue => match filter.operation {
+                FilterOperation::Set => self::apply_selective_hue(filter, codec, image),
+                FilterOperation::Add | FilterOperation::Multiply => return Err(Error::invalid_filter(filter)),
+            },
+            FilterTarget::Quantize => match filter.operation {
+                FilterOperation::Set => self::apply_quantize(filter.value, image),
+                FilterOperation::Add | FilterOperation::Multiply => return Err(Error::invalid_filter(filter)),
+            },
+            FilterTarget::Blur => match filter.operation {
+                FilterOperation::Set => self::apply_blur(filter.value, image, blur),
+                FilterOperation::Add | FilterOperation::Multiply => return Err(Error::invalid_filter(filter)),
+            },
+            FilterTarget::Vignette => match filter.operation {
+                FilterOperation::Set => self::apply_vignette(filter.value, image),
+                FilterOperation::Add | FilterOperation::Multiply => return Err(Error::invalid_filter(filter)),
+            },
+        }
+    }
+
+    Ok(())
+}
+
+/// Sets the hue of every pixel within the filter's tolerance of its source hue to the filter's value.
+///
+/// Distances are measured around the color wheel, so a source hue of `350.0` also matches a hue of `5.0`. Pixels with
+/// no saturation have no meaningful hue, so they are never replaced. The filter's condition is also respected.
+fn apply_selective_hue<P: RgbPixel>(filter: Filter, codec: Codec, image: &mut Buffer<P>) {
+    self::map_pixels(image, codec, |hsva| {
+        let difference = (hsva.hue.into_positive_degrees() - filter.source_hue).rem_euclid(360.0);
+        let distance = difference.min(360.0 - difference);
+
+        if hsva.saturation > 0.0 && distance <= filter.tolerance && filter.condition.matches(&hsva.color) {
+            hsva.set_hue(filter.value);
+        }
+    });
+}
+
+/// The sampling factor used when building a quantized palette, where `1` samples every pixel and `30` is the fastest.
+const QUANTIZE_SAMPLING: i32 = 10;
+
+/// Reduces the image to at most the given number of distinct colors, between 1 and 256, keeping each pixel's alpha.
+///
+/// The palette is built from every non-transparent pixel, and each pixel is replaced with its nearest palette color.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn apply_quantize<P: RgbPixel>(colors: f32, image: &mut Buffer<P>) {
+    let colors = colors.round().clamp(1.0, 256.0) as usize;
+    let samples = image
+        .pixels()
+        .map(|pixel| pixel.to_rgba().0)
+        .filter(|[.., alpha]| *alpha > 0)
+        .flat_map(|[red, green, blue, _]| [red, green, blue, u8::MAX])
+        .collect::<Vec<_>>();
+
+    if samples.is_empty() {
+        return;
+    }
+
+    let quantizer = NeuQuant::new(QUANTIZE_SAMPLING, colors, &samples);
+
+    for pixel in image.pixels_mut() {
+        let [red, green, blue, _] = pixel.to_rgba().0;
+        let index = quantizer.index_of(&[red, green, blue, u8::MAX]);
+
+        if let Some([red, green, blue, _]) = quantizer.lookup(index) {
+            pixel.channels_mut()[.. 3].copy_from_slice(&[red, green, blue]);
+        }
+    }
+}
+
+/// Applies a Gaussian blur with the given standard deviation, in pixels, to the image.
+///
+/// The color channels are premultiplied by alpha while blurring, so that the RGB components of transparent pixels
+/// never bleed into their neighbors and darken the edges of opaque areas. Pixels beyond the edges of the image repeat
+/// the nearest edge pixel. Non-positive deviations do nothing.
+///
+/// The intermediate pixels are stored within the given buffers, which are only reallocated if they are too small.
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss, clippy::cast_sign_loss)]
+fn apply_blur<P: RgbPixel>(sigma: f32, image: &mut Buffer<P>, buffers: &mut BlurBuffers) {
+    if sigma <= 0.0 {
+        return;
+    }
+
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    // Samples further than the image's size only ever repeat its edges, so the kernel never needs to be larger.
+    let radius = ((sigma * 3.0).ceil() as usize).min(width.max(height));
+    let BlurBuffers { kernel, pixels, horizontal } = buffers;
+
+    kernel.clear();
+    kernel.extend((0 ..= radius * 2).map(|index| {
+        let offset = index as f32 - radius as f32;
+
+        (-(offset * offset) / (2.0 * sigma * sigma)).exp()
+    }));
+
+    let total = kernel.iter().sum::<f32>();
+
+    for weight in kernel.iter_mut() {
+        *weight /= total;
+    }
+
+    pixels.clear();
+    pixels.extend(image.pixels().map(|pixel| {
+        let [red, green, blue, alpha] = pixel.to_rgba().0.map(|c| f32::from(c) / 255.0);
+
+        [red * alpha, green * alpha, blue * alpha, alpha]
+    }));
+    horizontal.clear();
+    horizontal.resize(pixels.len(), [0.0; 4]);
+
+    for (index, output) in horizontal.iter_mut().enumerate() {
+        let (x, row) = (index % width, index - (index % width));
+
+        *output = self::convolve(kernel, pixels, |offset| row + (x + offset).saturating_sub(radius).min(width - 1));
+    }
+
+    for (index, output) in pixels.iter_mut().enumerate() {
+        let (x, y) = (index % width, index / width);
+
+        *output = self::convolve(kernel, horizontal, |offset| {
+            ((y + offset).saturating_sub(radius).min(height - 1) * width) + x
+        });
+    }
+
+    for (pixel, blurred) in image.pixels_mut().zip(pixels.iter()) {
+        let [red, green, blue, alpha] = *blurred;
+        let rgb = if alpha > 0.0 { [red / alpha, green / alpha, blue / alpha] } else { [0.0; 3] };
+        let [red, green, blue] = rgb;
+        let rgba = [red, green, blue, alpha].map(|c| (c * 255.0).round().clamp(0.0, 255.0) as u8);
+        let channels = pixel.channels_mut();
+
+        // Pixels without an alpha channel simply drop it.
+        channels.copy_from_slice(&rgba[.. channels.len()]);
+    }
+}
+
+/// Returns the sum of the pixels at each index returned by the given function for each offset within the kernel,
+/// weighted by the kernel.
+fn convolve(kernel: &[f32], pixels: &[[f32; 4]], index: impl Fn(usize) -> usize) -> [f32; 4] {
+    let mut sum = [0.0_f32; 4];
+
+    for (offset, weight) in kernel.iter().enumerate() {
+        for (sum, channel) in sum.iter_mut().zip(pixels[index(offset)]) {
+            *sum = weight.mul_add(channel, *sum);
+        }
+    }
+
+    sum
+}
+
+/// Darkens each pixel according to its distance from the center of the image, scaled by the given strength.
+///
+/// The darkening falls off quadratically, so the center of the image is left as-is and the corners are darkened the
+/// most, becoming black at a strength of `1.0`. Each pixel's alpha is kept as-is.
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss, clippy::cast_sign_loss)]
+fn apply_vignette<P: RgbPixel>(strength: f32, image: &mut Buffer<P>) {
+    let (half_width, half_height) = (image.width() as f32 / 2.0, image.height() as f32 / 2.0);
+
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        // Each axis is normalized separately, so the falloff is elliptical for non-square images.
+        let dx = (x as f32 + 0.5 - half_width) / half_width;
+        let dy = (y as f32 + 0.5 - half_height) / half_height;
+        let factor = strength.mul_add(-dx.mul_add(dx, dy * dy) / 2.0, 1.0).clamp(0.0, 1.0);
+
+        for channel in pixel.channels_mut().iter_mut().take(3) {
+            *channel = (f32::from(*channel) * factor).round() as u8;
+        }
+    }
+}
+
+/// Randomly perturbs the saturation and brightness of each pixel by up to the given amplitude.
+///
+/// The perturbation is derived purely from the seed and each pixel's index, so the same seed will always produce the
+/// same output.
+fn apply_noise<P: RgbPixel>(seed: u64, amplitude: f32, codec: Codec, image: &mut Buffer<P>) {
+    let mut index = 0_u64;
+
+    self::map_pixels(image, codec, |hsva| {
+        let saturation = self::noise(seed, index.wrapping_mul(2));
+        let brightness = self::noise(seed, index.wrapping_mul(2).wrapping_add(1));
+
+        hsva.saturation = amplitude.mul_add(saturation, hsva.saturation).clamp(0.0, 1.0);
+        hsva.value = amplitude.mul_add(brightness, hsva.value).clamp(0.0, 1.0);
+
+        index += 1;
+    });
+}
+
+/// Returns pseudo-random bits for the given seed and index.
+///
+/// This uses the `SplitMix64` finalizer, which is cheap and stable across platforms and releases.
+const fn noise_bits(seed: u64, index: u64) -> u64 {
+    let mut z = seed.wrapping_add(index.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+
+    z ^ (z >> 31)
+}
+
+/// Returns a pseudo-random value within `-1.0..1.0` for the given seed and index.
+#[allow(clippy::cast_precision_loss)]
+fn noise(seed: u64, index: u64) -> f32 {
+    // Only the top 24 bits are used, as that is all an `f32` mantissa can represent exactly.
+    let unit = (self::noise_bits(seed, index) >> 40) as f32 / (1_u32 << 24) as f32;
+
+    unit.mul_add(2.0, -1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, LumaA};
+
+    use super::config::{Condition, MAX_FILTER_PASSES};
+    use super::*;
+
+    /// Returns a configuration that recolors towards a purple hue.
+    fn purple() -> DyeColorConfig {
+        DyeColorConfig::new([137, 50, 184])
+    }
+
+    /// Returns an sRGB color with the given 8-bit components.
+    fn srgb(rgb: [u8; 3]) -> Srgb {
+        Srgb::from_components(rgb.into()).into_format()
+    }
+
+    #[test]
+    fn error_diffusion_preserves_mean_of_flat_areas() {
+        let codec = Codec { dither: true, ..Codec::default() };
+        let mut diffusion = ErrorDiffusion::new(16);
+        let value = 100.25 / 255.0;
+        let pixels = (0 .. 16 * 16).map(|i| diffusion.quantize(codec, i % 16, [value; 3])[0]).collect::<Vec<_>>();
+
+        assert!(pixels.contains(&100) && pixels.contains(&101));
+
+        let mean = pixels.iter().map(|c| f32::from(*c)).sum::<f32>() / 256.0;
+
+        assert!((mean - 100.25).abs() < 0.1, "mean of {mean} is too far from 100.25");
+    }
+
+    #[test]
+    fn dithering_only_changes_quantization() -> Result<()> {
+        let gradient = RgbaImage::from_fn(32, 4, |x, _| Rgba([120 + u8::try_from(x / 4).unwrap_or(0), 60, 160, 255]));
+        let mut config = self::purple();
+
+        config.filters = [Filter::pixel_brightness_multiply(0.9)].into();
+
+        let mut banded = gradient.clone();
+
+        self::transform_image(&config, &mut banded)?;
+
+        config.dither = true;
+
+        let mut dithered = gradient;
+
+        self::transform_image(&config, &mut dithered)?;
+
+        assert_ne!(banded, dithered);
+
+        for (banded, dithered) in banded.pixels().zip(dithered.pixels()) {
+            for (banded, dithered) in banded.0.into_iter().zip(dithered.0) {
+                assert!(banded.abs_diff(dithered) <= 1);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn rgb_blend_interpolates_towards_target() -> Result<()> {
+        let mut config = self::purple();
+        let mut images = [0.0, 0.5, 1.0].map(|_| RgbaImage::from_pixel(1, 1, Rgba([200, 100, 0, 128])));
+
+        for (factor, image) in [0.0, 0.5, 1.0].into_iter().zip(&mut images) {
+            config.rgb_blend = Some(factor);
+
+            self::transform_image(&config, image)?;
+        }
+
+        let [unchanged, halfway, replaced] = images.map(|image| image.get_pixel(0, 0).0);
+
+        assert_eq!(unchanged, [200, 100, 0, 128]);
+        assert_eq!(halfway, [169, 75, 92, 128]);
+        assert_eq!(replaced, [137, 50, 184, 128]);
+
+        Ok(())
+    }
+
+    /// Returns an image with an opaque gradient surrounded by a fully transparent border.
+    fn bordered(width: u32, height: u32) -> RgbaImage {
+        RgbaImage::from_fn(width, height, |x, y| {
+            let opaque = (1 .. width - 1).contains(&x) && (1 .. height - 1).contains(&y);
+            let shade = u8::try_from((x * 16) + (y * 8)).unwrap_or(u8::MAX);
+
+            Rgba([shade, 64, 255 - shade, if opaque { 255 } else { 0 }])
+        })
+    }
+
+    #[test]
+    fn reusing_scratch_matches_fresh_transforms() -> Result<()> {
+        let mut config = self::purple();
+
+        config.filters = [Filter::image_blur_set(1.5), Filter::image_contrast_add(10.0)].into();
+        config.opaque_bounds_only = true;
+        config.lock = [FilterTarget::Brightness].into();
+        config.preserve_transparent_rgb = true;
+
+        let mut scratch = Scratch::new();
+
+        for image in [self::bordered(8, 8), self::bordered(5, 3), self::bordered(8, 8)] {
+            let (mut fresh, mut reused) = (image.clone(), image);
+
+            self::transform_image(&config, &mut fresh)?;
+            self::transform_image_with_scratch(&config, &mut reused, &mut scratch)?;
+
+            assert_eq!(fresh, reused);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn blur_does_not_darken_edges() {
+        let mut image = RgbaImage::from_fn(6, 6, |x, y| {
+            if (2 .. 4).contains(&x) && (2 .. 4).contains(&y) {
+                Rgba([200, 40, 120, 255])
+            } else {
+                Rgba([0; 4])
+            }
+        });
+
+        self::apply_blur(1.0, &mut image, &mut BlurBuffers::default());
+
+        for pixel in image.pixels().filter(|pixel| pixel.0[3] > 0) {
+            for (channel, expected) in pixel.0.into_iter().zip([200, 40, 120]) {
+                assert!(channel.abs_diff(expected) <= 1, "{pixel:?} was darkened");
+            }
+        }
+
+        assert!(image.get_pixel(0, 0).0[3] < image.get_pixel(2, 2).0[3]);
+    }
+
+    #[test]
+    fn blur_leaves_flat_images_unchanged() {
+        let original = RgbaImage::from_pixel(4, 3, Rgba([90, 180, 30, 200]));
+        let mut image = original.clone();
+
+        self::apply_blur(2.0, &mut image, &mut BlurBuffers::default());
+
+        assert_eq!(image, original);
+    }
+
+    #[test]
+    fn non_finite_filter_values_are_rejected() {
+        for value in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            let filter = Filter::pixel_brightness_add(value);
+            let mut hsva = Hsva::new(0.0, 0.5, 0.5, 1.0);
+
+            assert!(matches!(self::apply_pixel_filter(filter, &mut hsva), Err(Error::NonFiniteValue(..))));
+            assert!(matches!(
+                self::apply_image_filter(filter, &mut RgbaImage::new(1, 1)),
+                Err(Error::NonFiniteValue(..))
+            ));
+        }
+    }
+
+    #[test]
+    fn perceptual_metrics_match_near_colors_that_euclidean_rejects() {
+        let (a, b) = (self::srgb([200, 30, 30]), self::srgb([215, 30, 30]));
+
+        assert!(self::color_distance(DistanceMetric::Euclidean, a, b) > 5.0);
+        assert!(self::color_distance(DistanceMetric::Weighted, a, b) > 5.0);
+        assert!(self::color_distance(DistanceMetric::Cie76, a, b) > 5.0);
+        assert!(self::color_distance(DistanceMetric::Ciede2000, a, b) <= 5.0);
+    }
+
+    #[test]
+    fn color_map_uses_distance_metric() -> Result<()> {
+        let mut config = self::purple();
+
+        config.color_map = [ColorMapping { from: [215, 30, 30], to: [0, 0, 255], tolerance: 5.0 }].into();
+
+        let mut euclidean = RgbaImage::from_pixel(1, 1, Rgba([200, 30, 30, 255]));
+
+        self::transform_image(&config, &mut euclidean)?;
+
+        assert_ne!(euclidean.get_pixel(0, 0).0, [0, 0, 255, 255]);
+
+        config.distance_metric = DistanceMetric::Ciede2000;
+
+        let mut ciede2000 = RgbaImage::from_pixel(1, 1, Rgba([200, 30, 30, 255]));
+
+        self::transform_image(&config, &mut ciede2000)?;
+
+        assert_eq!(ciede2000.get_pixel(0, 0).0, [0, 0, 255, 255]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn chroma_key_uses_distance_metric() -> Result<()> {
+        let mut config = self::purple();
+
+        config.chroma_key = Some([215, 30, 30]);
+        config.chroma_key_tolerance = 5;
+
+        let mut euclidean = RgbaImage::from_pixel(1, 1, Rgba([200, 30, 30, 255]));
+
+        self::transform_image(&config, &mut euclidean)?;
+
+        assert_eq!(euclidean.get_pixel(0, 0).0[3], 255);
+
+        config.distance_metric = DistanceMetric::Ciede2000;
+
+        let mut ciede2000 = RgbaImage::from_pixel(1, 1, Rgba([200, 30, 30, 255]));
+
+        self::transform_image(&config, &mut ciede2000)?;
+
+        assert_eq!(ciede2000.get_pixel(0, 0).0[3], 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn gray_images_match_the_rgba_pipeline() -> Result<()> {
+        let mut config = self::purple();
+
+        config.filters = [Filter::image_brightness_add(20.0), Filter::image_contrast_add(10.0)].into();
+        config.allow_alpha = true;
+        config.alpha_scale = 0.5;
+
+        let shade = |x: u32| u8::try_from(x * 30).unwrap_or(u8::MAX);
+        let mut gray = GrayAlphaImage::from_fn(8, 1, |x, _| LumaA([shade(x), 200]));
+        let mut rgba = RgbaImage::from_fn(8, 1, |x, _| Rgba([shade(x), shade(x), shade(x), 200]));
+
+        self::transform_gray_image(&config, &mut gray)?;
+        self::transform_image(&config, &mut rgba)?;
+
+        for (gray, rgba) in gray.pixels().zip(rgba.pixels()) {
+            assert_eq!(gray.0, [rgba.0[0], rgba.0[3]]);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn gray_images_reject_rgb_only_options() {
+        let mut image = GrayAlphaImage::from_pixel(2, 2, LumaA([100, 255]));
+        let mut config = self::purple();
+
+        assert!(self::transform_gray_image(&config, &mut image).is_ok());
+
+        config.dither = true;
+
+        let result = self::transform_gray_image(&config, &mut image);
+
+        assert!(matches!(result, Err(Error::UnsupportedGrayOption("dither"))));
+
+        config.dither = false;
+        config.lock = [FilterTarget::Hue].into();
+
+        let result = self::transform_gray_image(&config, &mut image);
+
+        assert!(matches!(result, Err(Error::UnsupportedGrayOption("lock"))));
+
+        let recolors: [fn(&mut DyeColorConfig); 4] = [
+            |config| config.rgb_blend = Some(0.5),
+            |config| config.min_saturation = 0.5,
+            |config| config.color_map = [ColorMapping { from: [100; 3], to: [137, 50, 184], tolerance: 0.0 }].into(),
+            |config| config.filters = [Filter::pixel_hue_set(120.0)].into(),
+        ];
+
+        for recolor in recolors {
+            let mut config = self::purple();
+
+            recolor(&mut config);
+
+            let result = self::transform_gray_image(&config, &mut image);
+
+            assert!(matches!(result, Err(Error::RecolorsGrayscale)), "{config:?}");
+        }
+    }
+
+    #[test]
+    fn blend_masked_reports_which_dimensions_differ() {
+        let mut image = RgbaImage::new(2, 2);
+        let original = image.clone();
+
+        let result = self::blend_masked(&mut image, &RgbaImage::new(1, 1), &GrayImage::new(2, 2));
+
+        assert!(matches!(result, Err(Error::OriginalDimensions((1, 1), (2, 2)))));
+
+        let result = self::blend_masked(&mut image, &original, &GrayImage::new(1, 1));
+
+        assert!(matches!(result, Err(Error::MaskDimensions((1, 1), (2, 2)))));
+    }
+
+    #[test]
+    fn apply_filters_keeps_the_original_hue() -> Result<()> {
+        let mut image = RgbaImage::from_pixel(2, 2, Rgba([200, 40, 40, 255]));
+
+        self::apply_filters(&[Filter::pixel_brightness_multiply(0.5), Filter::image_brightness_add(10.0)], &mut image)?;
+
+        assert_eq!(image.get_pixel(0, 0).0, [110, 30, 30, 255]);
+
+        let filter = Filter::new(FilterType::Pixel, FilterTarget::Contrast, FilterOperation::Add, 1.0);
+        let result = self::apply_filters(&[filter], &mut image);
+
+        assert!(matches!(result, Err(Error::InvalidFilter(FilterType::Pixel, FilterTarget::Contrast, _))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn noise_is_determined_by_its_seed() -> Result<()> {
+        let base = RgbaImage::from_pixel(8, 8, Rgba([120, 80, 160, 255]));
+        let [first, second, other] = [1, 1, 2].map(|seed| {
+            let mut image = base.clone();
+
+            self::apply_image_filter(Filter::image_noise_add(0.1, seed), &mut image).map(|()| image)
+        });
+        let (first, second, other) = (first?, second?, other?);
+
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+        assert_ne!(first, base);
+
+        for value in (0 .. 1000).map(|index| self::noise(7, index)) {
+            assert!((-1.0 .. 1.0).contains(&value), "{value} is out of range");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn default_base_image_matches_the_resource() -> Result<()> {
+        let image = self::default_base_image()?;
+        let resource = image::open(concat!(env!("CARGO_MANIFEST_DIR"), "/res/amethyst.png"))?.to_rgba8();
+
+        assert_eq!(image.dimensions(), (16, 16));
+        assert_eq!(image, resource);
+
+        Ok(())
+    }
+
+    #[test]
+    fn gamut_compression_preserves_hue() {
+        let color = Srgba::new(1.4, 0.6, 0.2, 0.5);
+        let clipped = Codec { gamut_mapping: GamutMapping::Clip, ..Codec::default() }.map_gamut(color);
+        let compressed = Codec { gamut_mapping: GamutMapping::Compress, ..Codec::default() }.map_gamut(color);
+        let hue = |color: Srgba<f32>| Hsv::from_color(color.color).hue.into_positive_degrees();
+
+        assert_eq!(clipped, Srgba::new(1.0, 0.6, 0.2, 0.5));
+        assert!(compressed.clamp() == compressed, "{compressed:?} is out of gamut");
+        assert!((hue(compressed) - hue(color)).abs() < 0.01);
+        assert!((hue(clipped) - hue(color)).abs() > 1.0);
+        assert!((compressed.alpha - 0.5).abs() < f32::EPSILON);
+
+        let in_gamut = Srgba::new(0.3, 0.6, 0.9, 1.0);
+
+        assert_eq!(self::compress_gamut(in_gamut), in_gamut);
+    }
+
+    #[test]
+    fn precomputed_targets_match_the_configured_color() -> Result<()> {
+        let config = self::purple();
+        let mut expected = self::bordered(6, 6);
+        let mut actual = expected.clone();
+
+        self::transform_image(&config, &mut expected)?;
+        self::transform_image_with_target(&config, config.target_hsv(), &mut actual)?;
+
+        assert_eq!(expected, actual);
+
+        let mut shifted = self::bordered(6, 6);
+        let target = Hsv::new(120.0, config.target_hsv().saturation, config.target_hsv().value);
+
+        self::transform_image_with_target(&config, target, &mut shifted)?;
+
+        assert_ne!(expected, shifted);
+
+        Ok(())
+    }
+
+    #[test]
+    fn histograms_are_matched_to_the_reference() {
+        let gray = |value: u8, alpha: u8| Rgba([value, value, value, alpha]);
+        let level = |x: u32| 100 + u8::try_from(x).unwrap_or(0);
+        let mut image = RgbaImage::from_fn(4, 2, |x, y| if y == 0 { gray(50, 255) } else { gray(level(x), 255) });
+        let reference = RgbaImage::from_fn(4, 2, |_, y| if y == 0 { gray(150, 255) } else { gray(200, 255) });
+
+        image.put_pixel(0, 0, gray(10, 0));
+
+        self::match_histogram(&mut image, &reference);
+
+        assert_eq!(image.get_pixel(0, 0).0, [10, 10, 10, 0]);
+        assert!(image.rows().nth(1).into_iter().flatten().all(|pixel| pixel.0 == [200, 200, 200, 255]));
+        assert!(image.rows().next().into_iter().flatten().skip(1).all(|pixel| pixel.0 == [150, 150, 150, 255]));
+    }
+
+    #[test]
+    fn dominant_color_ignores_transparent_pixels() {
+        let mut image = RgbaImage::from_pixel(2, 2, Rgba([255, 255, 255, 0]));
+
+        assert_eq!(self::dominant_color(&image), [0, 0, 0]);
+
+        image.put_pixel(0, 0, Rgba([100, 0, 50, 255]));
+        image.put_pixel(1, 0, Rgba([201, 10, 50, 1]));
+
+        assert_eq!(self::dominant_color(&image), [151, 5, 50]);
+    }
+
+    #[test]
+    fn locked_components_keep_their_original_values() -> Result<()> {
+        let mut config = self::purple();
+
+        config.filters = [Filter::pixel_saturation_multiply(0.5)].into();
+        config.lock = [FilterTarget::Hue, FilterTarget::Brightness].into();
+
+        let mut image = RgbaImage::from_pixel(1, 1, Rgba([200, 40, 40, 255]));
+
+        self::transform_image(&config, &mut image)?;
+
+        let [red, green, blue, _] = image.get_pixel(0, 0).0;
+        let hsv = Hsv::from_color(self::srgb([red, green, blue]));
+
+        assert!(hsv.hue.into_degrees().abs() < 1.0, "{hsv:?}");
+        assert!((hsv.saturation - 0.9).abs() < 0.01, "{hsv:?}");
+        assert_eq!(red, 200);
+
+        config.lock = [].into();
+
+        self::transform_image(&config, &mut image)?;
+
+        let [red, green, blue, _] = image.get_pixel(0, 0).0;
+
+        assert!((Hsv::from_color(self::srgb([red, green, blue])).hue.into_positive_degrees() - 279.0).abs() < 1.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn image_hue_set_recolors_every_saturated_pixel() -> Result<()> {
+        let mut image = RgbaImage::new(3, 1);
+
+        image.put_pixel(0, 0, Rgba([200, 40, 40, 255]));
+        image.put_pixel(1, 0, Rgba([40, 40, 200, 128]));
+        image.put_pixel(2, 0, Rgba([90; 4]));
+
+        self::apply_image_filter(Filter::image_hue_set(120.0), &mut image)?;
+
+        let hsv = |x: u32| {
+            let [red, green, blue, _] = image.get_pixel(x, 0).0;
+
+            Hsv::from_color(self::srgb([red, green, blue]))
+        };
+
+        assert!((hsv(0).hue.into_positive_degrees() - 120.0).abs() < 1.0);
+        assert!((hsv(1).hue.into_positive_degrees() - 120.0).abs() < 1.0);
+        assert_eq!(image.get_pixel(0, 0).0, [40, 200, 40, 255]);
+        assert_eq!(image.get_pixel(1, 0).0[3], 128);
+        assert_eq!(image.get_pixel(2, 0).0, [90; 4]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn rounding_modes_quantize_channels() {
+        let quantize = |rounding, channel: f32| Codec { rounding, ..Codec::default() }.quantize(channel / 255.0);
+
+        assert_eq!(quantize(Rounding::Round, 100.5), 101);
+        assert_eq!(quantize(Rounding::Floor, 100.9), 100);
+        assert_eq!(quantize(Rounding::Ceil, 100.1), 101);
+        assert_eq!(quantize(Rounding::NearestEven, 100.5), 100);
+        assert_eq!(quantize(Rounding::NearestEven, 101.5), 102);
+        assert_eq!(quantize(Rounding::Ceil, 300.0), 255);
+        assert_eq!(quantize(Rounding::Floor, -5.0), 0);
+    }
+
+    #[test]
+    fn alpha_weighted_filters_scale_with_alpha() -> Result<()> {
+        let filter = Filter::pixel_brightness_set(0.0).weighted_by_alpha();
+        let hsva = |alpha: f32| Hsva::new(200.0, 0.5, 0.8, alpha);
+        let [mut opaque, mut translucent, mut transparent] = [1.0, 0.5, 0.0].map(hsva);
+
+        self::apply_pixel_filter(filter, &mut opaque)?;
+        self::apply_pixel_filter(filter, &mut translucent)?;
+        self::apply_pixel_filter(filter, &mut transparent)?;
+
+        assert!(opaque.value.abs() < f32::EPSILON);
+        assert!((translucent.value - 0.4).abs() < f32::EPSILON);
+        assert!((transparent.value - 0.8).abs() < f32::EPSILON);
+
+        let mut shifted = hsva(0.5);
+
+        self::apply_pixel_filter(Filter::pixel_hue_add(-60.0).weighted_by_alpha(), &mut shifted)?;
+
+        assert!((shifted.hue.into_positive_degrees() - 170.0).abs() < 0.01);
+
+        Ok(())
+    }
+
+    #[test]
+    fn supported_formats_can_be_encoded_and_decoded() -> Result<()> {
+        assert_eq!(self::detect_format(DEFAULT_BASE_IMAGE), Some(ImageFormat::Png));
+        assert_eq!(self::detect_format(b"not an image"), None);
+
+        let image = DynamicImage::ImageRgba8(self::bordered(3, 2));
+
+        for format in self::supported_output_formats().iter().copied() {
+            let mut bytes = std::io::Cursor::new(Vec::new());
+
+            image.write_to(&mut bytes, format)?;
+
+            assert!(self::supported_input_formats().contains(&format));
+            assert_eq!(image::load_from_memory_with_format(bytes.get_ref(), format)?, image);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn passes_repeat_filters() -> Result<()> {
+        let base = self::bordered(4, 4);
+        let (mut repeated, mut once) = (base.clone(), base);
+
+        self::apply_image_filter(Filter::image_brightness_add(10.0).with_passes(3), &mut repeated)?;
+        self::apply_image_filter(Filter::image_brightness_add(30.0), &mut once)?;
+
+        assert_eq!(repeated, once);
+
+        // Blurring is not additive, so two half-strength passes differ from a single full-strength pass.
+        let base = self::bordered(8, 8);
+        let (mut repeated, mut half, mut once) = (base.clone(), base.clone(), base);
+
+        self::apply_image_filter(Filter::image_blur_set(1.0).with_passes(2), &mut repeated)?;
+        self::apply_image_filter(Filter::image_blur_set(1.0), &mut half)?;
+        self::apply_image_filter(Filter::image_blur_set(2.0), &mut once)?;
+
+        assert_ne!(repeated, half);
+        assert_ne!(repeated, once);
+
+        let mut hsva = Hsva::new(10.0, 0.5, 0.5, 1.0);
+
+        self::apply_pixel_filter(Filter::pixel_hue_add(20.0).with_passes(4), &mut hsva)?;
+
+        assert!((hsva.hue.into_positive_degrees() - 90.0).abs() < 0.01);
+
+        for passes in [0, MAX_FILTER_PASSES + 1] {
+            let mut config = self::purple();
+
+            config.filters = [Filter::pixel_hue_add(1.0).with_passes(passes)].into();
+
+            assert!(matches!(config.validate(DyeColor::Purple), Err(Error::InvalidPasses(DyeColor::Purple, 0, _))));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn rgb_images_match_opaque_rgba_images() -> Result<()> {
+        let mut config = self::purple();
+
+        config.filters = [Filter::pixel_saturation_multiply(0.5), Filter::image_blur_set(1.0)].into();
+
+        let mut rgba = RgbaImage::from_fn(6, 4, |x, y| Rgba([u8::try_from(x * 40 + y).unwrap_or(0), 90, 30, 255]));
+        let mut rgb = DynamicImage::ImageRgba8(rgba.clone()).into_rgb8();
+
+        self::transform_image(&config, &mut rgba)?;
+        self::transform_rgb_image(&config, &mut rgb)?;
+
+        assert_eq!(DynamicImage::ImageRgba8(rgba).into_rgb8(), rgb);
+
+        Ok(())
+    }
+
+    #[test]
+    fn hue_multiply_wraps_around_the_color_wheel() -> Result<()> {
+        let cases = [(350.0, 2.0, 340.0), (-10.0, 2.0, 340.0), (90.0, 0.5, 45.0), (120.0, 3.0, 0.0)];
+
+        for (hue, factor, expected) in cases {
+            let mut hsva = Hsva::new(hue, 0.5, 0.5, 1.0);
+
+            self::apply_pixel_filter(Filter::pixel_hue_multiply(factor), &mut hsva)?;
+
+            // A result of `0.0` may be stored as `360.0`, since they are the same hue.
+            let difference = (hsva.hue.into_positive_degrees() - expected).rem_euclid(360.0);
+
+            assert!(difference.min(360.0 - difference) < 0.01, "{hue} * {factor} = {:?}", hsva.hue);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn borders_surround_the_image() {
+        let image = self::bordered(4, 3);
+        let framed = self::add_border(&image, 2, [137, 50, 184]);
+
+        assert_eq!(framed.dimensions(), (8, 7));
+        assert_eq!(framed.view(2, 2, 4, 3).to_image(), image);
+
+        for (x, y, pixel) in framed.enumerate_pixels() {
+            if !(2 .. 6).contains(&x) || !(2 .. 5).contains(&y) {
+                assert_eq!(pixel.0, [137, 50, 184, 255]);
+            }
+        }
+
+        assert_eq!(self::add_border(&image, 0, [0; 3]), image);
+    }
+
+    #[test]
+    fn palettes_are_sampled_from_each_cell() -> Result<()> {
+        // Only the center of each 3x2 cell is colored, so sampling any other pixel would return transparent black.
+        let image = RgbaImage::from_fn(12, 8, |x, y| {
+            let index = u8::try_from((y / 2) * 4 + (x / 3)).unwrap_or(0);
+
+            if x % 3 == 1 && y % 2 == 1 { Rgba([index, index * 2, 255 - index, 255]) } else { Rgba([0; 4]) }
+        });
+        let palette = self::sample_palette(&image, 4, 4)?;
+
+        assert_eq!(palette.len(), 16);
+        assert_eq!(palette[&DyeColor::White], [0, 0, 255]);
+        assert_eq!(palette[&DyeColor::Purple], [13, 26, 242]);
+        assert_eq!(palette[&DyeColor::Pink], [15, 30, 240]);
+
+        assert!(matches!(self::sample_palette(&image, 4, 3), Err(Error::InvalidPaletteLayout(4, 3))));
+        assert!(matches!(self::sample_palette(&image, 16, 1), Err(Error::InvalidPaletteLayout(16, 1))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn chroma_keys_make_matching_pixels_transparent() -> Result<()> {
+        let mut config = self::purple();
+
+        config.chroma_key = Some([0, 255, 0]);
+        config.chroma_key_tolerance = 10;
+
+        let mut source = RgbaImage::new(3, 1);
+
+        // The second pixel is within a distance of 10 from the key, while the third is not.
+        source.put_pixel(0, 0, Rgba([0, 255, 0, 255]));
+        source.put_pixel(1, 0, Rgba([6, 248, 0, 255]));
+        source.put_pixel(2, 0, Rgba([20, 235, 0, 255]));
+
+        let mut image = source.clone();
+
+        self::transform_image(&config, &mut image)?;
+
+        assert_eq!(image.pixels().map(|pixel| pixel.0[3]).collect::<Vec<_>>(), [0, 0, 255]);
+
+        config.allow_alpha = false;
+
+        let mut image = source;
+
+        self::transform_image(&config, &mut image)?;
+
+        assert!(image.pixels().all(|pixel| pixel.0[3] == 255));
+
+        Ok(())
+    }
+
+    #[test]
+    fn luminance_is_balanced_towards_the_target() {
+        let mut image = RgbaImage::from_fn(4, 4, |x, _| Rgba([80, 60, u8::try_from(40 + x * 20).unwrap_or(0), 255]));
+
+        image.put_pixel(0, 0, Rgba([255, 255, 255, 0]));
+
+        self::balance_luminance(&mut image, 0.4);
+
+        let mean = self::mean_luminance(&image).unwrap_or_default();
+
+        assert!((mean - 0.4).abs() < 0.01, "mean luminance of {mean}");
+        assert_eq!(image.get_pixel(0, 0).0, [255, 255, 255, 0]);
+
+        // A nearly black image can only be brightened by the largest balance factor.
+        let mut dark = RgbaImage::from_pixel(1, 1, Rgba([2, 2, 2, 255]));
+
+        self::balance_luminance(&mut dark, 0.5);
+
+        assert_eq!(dark.get_pixel(0, 0).0, [4, 4, 4, 255]);
+        assert_eq!(self::mean_luminance(&RgbaImage::new(2, 2)), None);
+    }
+
+    #[test]
+    fn masks_limit_where_transforms_apply() -> Result<()> {
+        let config = self::purple();
+        let source = RgbaImage::from_pixel(3, 1, Rgba([200, 40, 40, 255]));
+        let mask = GrayImage::from_fn(3, 1, |x, _| Luma([[255, 0, 128][x as usize]]));
+        let mut transformed = source.clone();
+        let mut masked = source.clone();
+
+        self::transform_image(&config, &mut transformed)?;
+        self::transform_image_masked(&config, &mut masked, &mask)?;
+
+        assert_eq!(masked.get_pixel(0, 0), transformed.get_pixel(0, 0));
+        assert_eq!(masked.get_pixel(1, 0), source.get_pixel(1, 0));
+
+        let (blended, original) = (masked.get_pixel(2, 0).0, source.get_pixel(0, 0).0);
+
+        for ((blended, transformed), original) in blended.into_iter().zip(transformed.get_pixel(0, 0).0).zip(original) {
+            assert!(blended.abs_diff(transformed.midpoint(original)) <= 1);
+        }
+
+        let mut filtered = source.clone();
+
+        self::apply_image_filter_masked(Filter::image_brightness_add(20.0), &mut filtered, &mask)?;
+
+        assert_eq!(filtered.get_pixel(0, 0).0, [220, 60, 60, 255]);
+        assert_eq!(filtered.get_pixel(1, 0), source.get_pixel(1, 0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn alpha_masks_contain_each_alpha_channel() {
+        let image = self::bordered(4, 3);
+        let mask = self::alpha_mask(&image);
+
+        assert_eq!(mask.dimensions(), image.dimensions());
+        assert!(mask.pixels().zip(image.pixels()).all(|(mask, pixel)| mask.0[0] == pixel.0[3]));
+    }
+
+    #[test]
+    fn conditions_limit_pixel_filters() -> Result<()> {
+        let dark_only = Condition { max_value: Some(0.5), ..Condition::default() };
+        let filter = Filter::pixel_brightness_set(0.0).with_condition(dark_only);
+        let (mut dark, mut bright) = (Hsva::new(200.0, 0.5, 0.4, 1.0), Hsva::new(200.0, 0.5, 0.8, 1.0));
+
+        self::apply_pixel_filter(filter, &mut dark)?;
+        self::apply_pixel_filter(filter, &mut bright)?;
+
+        assert!(dark.value.abs() < f32::EPSILON);
+        assert!((bright.value - 0.8).abs() < f32::EPSILON);
+
+        let reds = Condition { min_hue: Some(330.0), max_hue: Some(30.0), ..Condition::default() };
+
+        for (hue, matches) in [(350.0, true), (0.0, true), (30.0, true), (180.0, false), (329.0, false)] {
+            assert_eq!(reds.matches(&Hsv::new(hue, 1.0, 1.0)), matches, "hue {hue}");
+        }
+
+        assert!(Condition::default().matches(&Hsv::new(123.0, 0.0, 0.0)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn empty_images_are_rejected() {
+        for (width, height) in [(0, 0), (0, 3), (3, 0)] {
+            let config = self::purple();
+            let is_empty =
+                |result: Result<()>| matches!(result, Err(Error::EmptyImage(w, h)) if (w, h) == (width, height));
+
+            assert!(is_empty(self::transform_image(&config, &mut RgbaImage::new(width, height))));
+            assert!(is_empty(self::transform_gray_image(&config, &mut GrayAlphaImage::new(width, height))));
+            assert!(is_empty(self::transform_image_staged(&config, &mut RgbaImage::new(width, height), |_, _| Ok(()))));
+        }
+    }
+
+    #[test]
+    fn single_pixel_images_are_transformed() -> Result<()> {
+        let mut config = self::purple();
+        let transform = |config: &DyeColorConfig| -> Result<Rgba<u8>> {
+            let mut image = RgbaImage::from_pixel(1, 1, Rgba([200, 100, 0, 255]));
+
+            self::transform_image(config, &mut image)?;
+
+            Ok(*image.get_pixel(0, 0))
+        };
+
+        let recolored = transform(&config)?;
+
+        // A lone pixel has no neighbors, so blurring leaves it as-is.
+        config.filters = [Filter::image_blur_set(2.0)].into();
+
+        assert_eq!(transform(&config)?, recolored);
+
+        config.filters = [
+            Filter::image_contrast_add(0.5),
+            Filter::image_noise_add(0.1, 7),
+            Filter::image_quantize_set(2.0),
+            Filter::image_vignette_set(1.0),
+        ]
+        .into();
+
+        assert_eq!(transform(&config)?.0[3], 255);
+
+        let mut gray = GrayAlphaImage::from_pixel(1, 1, LumaA([90, 255]));
+
+        config.filters = [Filter::image_brightness_add(10.0)].into();
+
+        self::transform_gray_image(&config, &mut gray)?;
+
+        assert_eq!(gray.get_pixel(0, 0).0, [100, 255]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn image_hue_add_rotates_and_wraps() -> Result<()> {
+        let rotate = |degrees: f32, image: &RgbaImage| -> Result<RgbaImage> {
+            let mut image = image.clone();
+
+            self::apply_image_filter(Filter::image_hue_add(degrees), &mut image)?;
+
+            Ok(image)
+        };
+
+        // Rotating towards larger angles moves red towards green.
+        let red = RgbaImage::from_pixel(1, 1, Rgba([255, 0, 0, 255]));
+
+        assert_eq!(rotate(120.0, &red)?.get_pixel(0, 0).0, [0, 255, 0, 255]);
+        assert_eq!(rotate(-120.0, &red)?.get_pixel(0, 0).0, [0, 0, 255, 255]);
+
+        let base = self::bordered(6, 4);
+
+        assert_eq!(rotate(360.0, &base)?, base);
+        assert_eq!(rotate(-360.0, &base)?, base);
+        assert_eq!(rotate(-30.0, &base)?, rotate(330.0, &base)?);
+        assert_eq!(rotate(750.0, &base)?, rotate(30.0, &base)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn selective_hue_only_replaces_nearby_hues() -> Result<()> {
+        let hues = [5.0, 340.0, 20.0, 240.0];
+        let mut image = RgbaImage::from_fn(5, 1, |x, _| {
+            let Some(&hue) = hues.get(x as usize) else {
+                return Rgba([90, 90, 90, 255]);
+            };
+            let [red, green, blue] = Srgb::from_color(Hsv::new(hue, 1.0, 1.0)).into_format::<u8>().into();
+
+            Rgba([red, green, blue, 255])
+        });
+
+        // The range wraps around `360.0`, so both 5 and 340 degrees are within 20 degrees of 350 degrees.
+        self::apply_image_filter(Filter::image_selective_hue_set(350.0, 20.0, 120.0), &mut image)?;
+
+        let hue = |x: u32| {
+            let [red, green, blue, _] = image.get_pixel(x, 0).0;
+
+            Hsv::from_color(self::srgb([red, green, blue])).hue.into_positive_degrees()
+        };
+
+        assert!((hue(0) - 120.0).abs() < 1.0);
+        assert!((hue(1) - 120.0).abs() < 1.0);
+        assert!((hue(2) - 20.0).abs() < 1.0);
+        assert!((hue(3) - 240.0).abs() < 1.0);
+        assert_eq!(image.get_pixel(4, 0).0, [90, 90, 90, 255]);
+
+        let bright = Condition { min_value: Some(0.9), ..Condition::default() };
+        let mut image = RgbaImage::from_pixel(2, 1, Rgba([100, 0, 0, 255]));
+
+        image.put_pixel(0, 0, Rgba([255, 0, 0, 255]));
+
+        self::apply_image_filter(Filter::image_selective_hue_set(0.0, 10.0, 240.0).with_condition(bright), &mut image)?;
+
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 255, 255]);
+        assert_eq!(image.get_pixel(1, 0).0, [100, 0, 0, 255]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn vignettes_darken_towards_the_corners() -> Result<()> {
+        let base = RgbaImage::from_pixel(4, 4, Rgba([200, 160, 120, 100]));
+        let vignette = |strength: f32| -> Result<RgbaImage> {
+            let mut image = base.clone();
+
+            self::apply_image_filter(Filter::image_vignette_set(strength), &mut image)?;
+
+            Ok(image)
+        };
+
+        assert_eq!(vignette(0.0)?, base);
+
+        let image = vignette(1.0)?;
+        let red = |x: u32, y: u32| image.get_pixel(x, y).0[0];
+
+        assert_eq!(red(1, 1), 188);
+        assert_eq!(red(0, 0), 88);
+        assert!(red(0, 1) > red(0, 0) && red(0, 1) < red(1, 1));
+        assert!([(3, 0), (0, 3), (3, 3)].into_iter().all(|(x, y)| red(x, y) == red(0, 0)));
+        assert!(image.pixels().all(|pixel| pixel.0[3] == 100));
+
+        let image = vignette(4.0)?;
+
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 0, 100]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn alpha_scale_multiplies_each_alpha() -> Result<()> {
+        let alphas = [0, 100, 200, 255];
+        let transform = |alpha_scale: f32, allow_alpha: bool| -> Result<Vec<u8>> {
+            let config = DyeColorConfig { allow_alpha, alpha_scale, ..self::purple() };
+            let mut image = RgbaImage::from_fn(4, 1, |x, _| Rgba([200, 100, 0, alphas[x as usize]]));
+
+            self::transform_image(&config, &mut image)?;
+
+            Ok(image.pixels().map(|pixel| pixel.0[3]).collect())
+        };
+
+        assert_eq!(transform(1.0, true)?, alphas);
+        assert_eq!(transform(0.5, true)?, [0, 50, 100, 128]);
+        assert_eq!(transform(2.0, true)?, [0, 200, 255, 255]);
+        assert_eq!(transform(2.0, false)?, transform(1.0, false)?);
+
+        for alpha_scale in [-0.5, f32::NAN, f32::INFINITY] {
+            let config = DyeColorConfig { alpha_scale, ..self::purple() };
+
+            assert!(matches!(config.validate(DyeColor::Purple), Err(Error::InvalidAlphaScale(DyeColor::Purple, _))));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn mipmaps_halve_each_level() {
+        let sizes = |image: &RgbaImage| self::mipmaps(image).iter().map(RgbaImage::dimensions).collect::<Vec<_>>();
+
+        assert_eq!(sizes(&RgbaImage::new(8, 4)), [(4, 2), (2, 1), (1, 1)]);
+        assert_eq!(sizes(&RgbaImage::new(5, 3)), [(2, 1), (1, 1)]);
+        assert!(sizes(&RgbaImage::new(1, 1)).is_empty());
+        assert!(sizes(&RgbaImage::new(0, 4)).is_empty());
+        assert!(sizes(&RgbaImage::new(4, 0)).is_empty());
+        assert!(sizes(&RgbaImage::new(0, 0)).is_empty());
+
+        // Transparent pixels are excluded from the average color, but still count towards the average alpha.
+        let mut image = RgbaImage::from_pixel(2, 2, Rgba([0, 0, 0, 0]));
+
+        image.put_pixel(0, 0, Rgba([200, 40, 40, 255]));
+        image.put_pixel(1, 1, Rgba([100, 40, 40, 255]));
+
+        assert_eq!(self::mipmaps(&image).first().map(|level| level.get_pixel(0, 0).0), Some([150, 40, 40, 128]));
+    }
+
+    #[test]
+    fn transforming_into_a_destination_keeps_the_source() -> Result<()> {
+        let config = self::purple();
+        let source = self::bordered(6, 6);
+        let mut expected = source.clone();
+        let mut destination = RgbaImage::from_pixel(6, 6, Rgba([1, 2, 3, 4]));
+
+        self::transform_image(&config, &mut expected)?;
+        self::transform_pixels_into(&config, &source, &mut destination)?;
+
+        assert_eq!(source, self::bordered(6, 6));
+        assert_eq!(destination, expected);
+
+        let mut mismatched = RgbaImage::new(6, 5);
+
+        assert!(matches!(
+            self::transform_pixels_into(&config, &source, &mut mismatched),
+            Err(Error::DestinationDimensions((6, 5), (6, 6)))
+        ));
+        assert_eq!(mismatched, RgbaImage::new(6, 5));
+
+        Ok(())
+    }
+
+    #[test]
+    fn min_saturation_recolors_gray_pixels() -> Result<()> {
+        let gray = RgbaImage::from_pixel(2, 2, Rgba([128, 128, 128, 255]));
+        let mut config = self::purple();
+        let mut unchanged = gray.clone();
+
+        self::transform_image(&config, &mut unchanged)?;
+
+        assert_eq!(unchanged, gray);
+
+        config.min_saturation = 0.4;
+
+        let mut recolored = gray;
+
+        self::transform_image(&config, &mut recolored)?;
+
+        for pixel in recolored.pixels() {
+            let [red, green, blue, _] = pixel.0;
+            let hsv: Hsv = Hsv::from_color(self::srgb([red, green, blue]));
+            let target = config.target_hsv().hue.into_positive_degrees();
+
+            assert!((hsv.hue.into_positive_degrees() - target).abs() < 1.0, "{hsv:?}");
+            assert!((hsv.saturation - 0.4).abs() < 0.01, "{hsv:?}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn quantizing_limits_the_opaque_colors() -> Result<()> {
+        let source = RgbaImage::from_fn(16, 16, |x, y| {
+            let channel = |value: u32| u8::try_from(value * 16).unwrap_or(u8::MAX);
+
+            Rgba([channel(x), channel(y), channel(15 - x), if x == 0 { 0 } else { 255 - channel(y) }])
+        });
+
+        for colors in [1_u8, 4, 16] {
+            let mut image = source.clone();
+
+            self::apply_image_filter(Filter::image_quantize_set(f32::from(colors)), &mut image)?;
+
+            let opaque = image
+                .pixels()
+                .filter(|pixel| pixel.0[3] > 0)
+                .map(|pixel| pixel.to_rgb().0)
+                .collect::<std::collections::BTreeSet<_>>();
+
+            assert!(opaque.len() <= usize::from(colors), "{} > {colors}", opaque.len());
+            assert!(image.pixels().zip(source.pixels()).all(|(a, b)| a.0[3] == b.0[3]));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn transparent_rgb_is_preserved_when_configured() -> Result<()> {
+        let source = self::bordered(6, 6);
+        let mut config = self::purple();
+        let (mut recolored, mut preserved) = (source.clone(), source.clone());
+
+        self::transform_image(&config, &mut recolored)?;
+
+        config.preserve_transparent_rgb = true;
+
+        self::transform_image(&config, &mut preserved)?;
+
+        let transparent = |image: &RgbaImage| image.pixels().filter(|p| p.0[3] == 0).copied().collect::<Vec<_>>();
+        let opaque = |image: &RgbaImage| image.pixels().filter(|p| p.0[3] > 0).copied().collect::<Vec<_>>();
+
+        assert!(!transparent(&source).is_empty());
+        assert_ne!(transparent(&recolored), transparent(&source));
+        assert_eq!(transparent(&preserved), transparent(&source));
+        assert_eq!(opaque(&preserved), opaque(&recolored));
+
+        Ok(())
+    }
+
+    #[test]
+    fn hue_corrections_shift_the_output_hue() -> Result<()> {
+        let mut config = DyeColorConfig::new([60, 68, 170]);
+        let hue = |config: &DyeColorConfig| -> Result<f32> {
+            let mut image = RgbaImage::from_pixel(1, 1, Rgba([200, 40, 40, 255]));
+
+            self::transform_image(config, &mut image)?;
+
+            let [red, green, blue, _] = image.get_pixel(0, 0).0;
+            let hsv: Hsv = Hsv::from_color(self::srgb([red, green, blue]));
+
+            Ok(hsv.hue.into_positive_degrees())
+        };
+
+        let uncorrected = hue(&config)?;
+
+        config.hue_correction = 12.0;
+
+        let corrected = hue(&config)?;
+
+        assert!((uncorrected - 235.0).abs() < 1.0, "{uncorrected}");
+        assert!((corrected - uncorrected - 12.0).abs() < 1.0, "{corrected} - {uncorrected}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn image_filters_can_skip_pixels_outside_the_opaque_bounds() -> Result<()> {
+        let inside = |x: u32, y: u32| (3 ..= 6).contains(&x) && (2 ..= 4).contains(&y);
+        let source = RgbaImage::from_fn(10, 8, |x, y| {
+            if inside(x, y) { Rgba([180, 60, 90, if x == 3 { 1 } else { 255 }]) } else { Rgba([50, 60, 70, 0]) }
+        });
+
+        assert_eq!(self::opaque_bounds(&source), Some(Rect { x: 3, y: 2, width: 4, height: 3 }));
+        assert_eq!(self::opaque_bounds(&RgbaImage::new(4, 4)), None);
+
+        let mut config = self::purple();
+        let mut unfiltered = source.clone();
+
+        self::transform_image(&config, &mut unfiltered)?;
+
+        config.filters = [Filter::image_brightness_add(40.0)].into();
+
+        let (mut full, mut bounded) = (source.clone(), source);
+
+        self::transform_image(&config, &mut full)?;
+
+        config.opaque_bounds_only = true;
+
+        self::transform_image(&config, &mut bounded)?;
+
+        for (x, y, pixel) in bounded.enumerate_pixels() {
+            if inside(x, y) {
+                assert_eq!(pixel, full.get_pixel(x, y));
+                assert_ne!(pixel, unfiltered.get_pixel(x, y));
+            } else {
+                assert_eq!(pixel, unfiltered.get_pixel(x, y));
+                assert_ne!(pixel, full.get_pixel(x, y));
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn hsv_targets_match_their_equivalent_rgb() -> Result<()> {
+        let json = r#"{ "colors": { "magenta": { "hsv": [300.0, 0.6, 1.0] }, "pink": { "rgb": [255, 102, 255] } } }"#;
+        let config = serde_json::from_str::<Config>(json)?;
+
+        config.validate()?;
+
+        let (hsv, rgb) = (&config.colors[&DyeColor::Magenta], &config.colors[&DyeColor::Pink]);
+        let (mut from_hsv, mut from_rgb) = (self::bordered(8, 8), self::bordered(8, 8));
+
+        assert_eq!(hsv.rgb, rgb.rgb);
+
+        self::transform_image(hsv, &mut from_hsv)?;
+        self::transform_image(rgb, &mut from_rgb)?;
+
+        assert_eq!(from_hsv, from_rgb);
+
+        Ok(())
+    }
+
+    #[test]
+    fn filters_before_the_hue_see_the_source_colors() -> Result<()> {
+        let condition = Condition { min_hue: Some(330.0), max_hue: Some(30.0), ..Condition::default() };
+        let mut config = self::purple();
+
+        config.filters = [Filter::pixel_saturation_set(0.0).with_condition(condition)].into();
+
+        let transform = |config: &DyeColorConfig| -> Result<RgbaImage> {
+            let mut image =
+                RgbaImage::from_fn(
+                    2,
+                    1,
+                    |x, _| {
+                        if x == 0 { Rgba([200, 40, 40, 255]) } else { Rgba([40, 40, 200, 255]) }
+                    },
+                );
+
+            self::transform_image(config, &mut image)?;
+
+            Ok(image)
+        };
+        let is_gray = |pixel: &Rgba<u8>| pixel.0[0] == pixel.0[1] && pixel.0[1] == pixel.0[2];
+
+        // After the hue is replaced, no pixel is red, so the filter never applies.
+        let after = transform(&config)?;
+
+        assert_eq!(config.filter_stage, FilterStage::AfterHue);
+        assert!(!after.pixels().any(is_gray));
+
+        config.filter_stage = FilterStage::BeforeHue;
+
+        let before = transform(&config)?;
+
+        assert!(is_gray(before.get_pixel(0, 0)));
+        assert!(!is_gray(before.get_pixel(1, 0)));
+        assert_ne!(before, after);
+
+        Ok(())
+    }
+
+    #[test]
+    fn transform_all_converts_every_color_in_order() -> Result<()> {
+        let config = Config::from_path(&std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("res/default.json"))?;
+        let base = self::default_base_image()?;
+        let variants = self::transform_all(&config, &base)?;
+        let colors = variants.iter().map(|(color, _)| *color).collect::<Vec<_>>();
+
+        assert_eq!(variants.len(), config.colors.len());
+        assert_eq!(colors, config.colors.keys().copied().collect::<Vec<_>>());
+        assert!(colors.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(base, self::default_base_image()?);
+
+        for (color, variant) in &variants {
+            let mut expected = base.clone();
+
+            self::transform_image(&config.colors[color], &mut expected)?;
+
+            assert_eq!(variant, &expected, "{color}");
+        }
+
+        assert_eq!(self::transform_all(&config, &base)?, variants);
+
+        Ok(())
+    }
+
+    #[test]
+    fn saturation_filters_stay_within_gamut() -> Result<()> {
+        let filters = [
+            (Filter::pixel_saturation_multiply(3.0), 1.0),
+            (Filter::pixel_saturation_multiply(-2.0), 0.0),
+            (Filter::pixel_saturation_add(5.0), 1.0),
+            (Filter::pixel_saturation_add(-5.0), 0.0),
+            (Filter::pixel_saturation_set(1.5), 1.0),
+        ];
+
+        for (filter, expected) in filters {
+            let mut hsva: Hsva<palette::encoding::Srgb> = Hsva::new(280.0, 0.9, 0.8, 1.0);
+
+            self::apply_pixel_filter(filter, &mut hsva)?;
+
+            assert!((hsva.saturation - expected).abs() < f32::EPSILON, "{filter:?} gave {}", hsva.saturation);
+        }
+
+        Ok(())
+    }
+}
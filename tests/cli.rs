@@ -0,0 +1,1100 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright © 2024 Jaxydog
+//
+// This file is part of Amethyst Colorizer.
+//
+// Amethyst Colorizer is free software: you can redistribute it and/or modify it under the terms of the GNU Affero General
+// Public License as published by the Free Software Foundation, either version 3 of the License, or (at your option) any
+// later version.
+//
+// Amethyst Colorizer is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without even the implied
+// warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU Affero General Public License for more
+// details.
+//
+// You should have received a copy of the GNU Affero General Public License along with Amethyst Colorizer. If not, see
+// <https://www.gnu.org/licenses/>.
+
+#![deny(clippy::expect_used, clippy::panic, clippy::unwrap_used, unsafe_code)]
+#![warn(clippy::nursery, clippy::todo, clippy::pedantic)]
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Returns the path of the given file within the repository.
+fn resource(path: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join(path)
+}
+
+/// Runs the command-line interface with the given arguments, returning its output.
+fn run(arguments: &[&str]) -> std::io::Result<Output> {
+    Command::new(env!("CARGO_BIN_EXE_amethyst-colorizer")).args(arguments).output()
+}
+
+#[test]
+fn print_effective_hue_requires_no_input() -> std::io::Result<()> {
+    let config = self::resource("res/default.json");
+    let output = self::run(&["--print-effective-hue", "--config", &config.to_string_lossy()])?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(stdout.lines().any(|line| line.starts_with("purple")));
+
+    Ok(())
+}
+
+#[test]
+fn json_errors_describe_the_failure() -> Result<(), Box<dyn std::error::Error>> {
+    let output = self::run(&["--use-default-base", "--json-errors", "--config", "missing.json"])?;
+    let error: serde_json::Value = serde_json::from_slice(&output.stderr)?;
+
+    assert!(!output.status.success());
+    assert_eq!(error["code"], "missing_file");
+    assert_eq!(error["context"]["file"], "missing.json");
+
+    Ok(())
+}
+
+#[test]
+fn gray_inputs_are_promoted_only_for_rgb_options() -> Result<(), Box<dyn std::error::Error>> {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("gray_inputs");
+    let input = directory.join("gray.png");
+    let config = directory.join("config.json");
+
+    std::fs::create_dir_all(&directory)?;
+    image::GrayImage::from_fn(4, 4, |x, _| image::Luma([u8::try_from(x * 30).unwrap_or(u8::MAX)])).save(&input)?;
+
+    for (dither, expected) in [(false, image::ColorType::L8), (true, image::ColorType::Rgba8)] {
+        let output = directory.join(format!("out_{dither}"));
+        let filter = r#"{ "type": "image", "target": "brightness", "operation": "add", "value": 10.0 }"#;
+        let color = format!(r#"{{ "rgb": [137, 50, 184], "dither": {dither}, "filters": [{filter}] }}"#);
+
+        std::fs::write(&config, format!(r#"{{ "colors": {{ "purple": {color} }} }}"#))?;
+
+        let result = self::run(&[
+            &input.to_string_lossy(),
+            "--config",
+            &config.to_string_lossy(),
+            "--target-color",
+            "purple",
+            "--output-dir",
+            &output.to_string_lossy(),
+        ])?;
+
+        assert!(result.status.success(), "{}", String::from_utf8_lossy(&result.stderr));
+        assert_eq!(image::open(output.join("purple_amethyst.png"))?.color(), expected);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn missing_job_files_have_their_own_error() -> std::io::Result<()> {
+    let output = self::run(&["job", "missing.toml"])?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("unable to find the job file at \"missing.toml\""), "{stderr}");
+
+    Ok(())
+}
+
+#[test]
+fn output_names_collide_regardless_of_case() -> Result<(), Box<dyn std::error::Error>> {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("output_collisions");
+    let config = directory.join("config.json");
+    let purple = r#""purple": { "rgb": [137, 50, 184], "output_name": "Violet" }"#;
+    let magenta = r#""magenta": { "rgb": [199, 78, 189], "output_name": "violet" }"#;
+
+    std::fs::create_dir_all(&directory)?;
+    std::fs::write(&config, format!(r#"{{ "colors": {{ {purple}, {magenta} }} }}"#))?;
+
+    let output = self::run(&[
+        "--use-default-base",
+        "--json-errors",
+        "--config",
+        &config.to_string_lossy(),
+        "--output-dir",
+        &directory.join("out").to_string_lossy(),
+    ])?;
+    let error: serde_json::Value = serde_json::from_slice(&output.stderr)?;
+
+    assert!(!output.status.success());
+    assert_eq!(error["code"], "output_collision");
+    assert_eq!(error["context"]["name"], "violet");
+
+    Ok(())
+}
+
+#[test]
+fn apng_output_has_a_transparent_frame_per_color() -> Result<(), Box<dyn std::error::Error>> {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("apng_output");
+    let input = directory.join("input.png");
+    let summary = directory.join("summary.json");
+    let config = self::resource("res/default.json");
+    let alpha = |x: u32| u8::try_from(x * 85).unwrap_or(u8::MAX);
+    let image = image::RgbaImage::from_fn(4, 4, |x, _| image::Rgba([200, 40, 120, alpha(x)]));
+
+    std::fs::create_dir_all(&directory)?;
+    image.save(&input)?;
+
+    let output = self::run(&[
+        &input.to_string_lossy(),
+        "--format",
+        "apng",
+        "--config",
+        &config.to_string_lossy(),
+        "--output-dir",
+        &directory.to_string_lossy(),
+        "--summary-json",
+        &summary.to_string_lossy(),
+    ])?;
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let colors = serde_json::from_slice::<serde_json::Value>(&std::fs::read(config)?)?["colors"]
+        .as_object()
+        .map_or(0, serde_json::Map::len);
+    let summary: serde_json::Value = serde_json::from_slice(&std::fs::read(summary)?)?;
+    let mut reader = png::Decoder::new(std::fs::File::open(directory.join("amethyst_animated.png"))?).read_info()?;
+    let frames = reader.info().animation_control().map_or(0, |control| control.num_frames);
+    let mut frame = vec![0; reader.output_buffer_size()];
+
+    reader.next_frame(&mut frame)?;
+
+    assert_eq!(usize::try_from(frames)?, colors);
+    assert_eq!(summary["outputs"].as_array().map_or(0, Vec::len), colors);
+    assert_eq!(reader.info().color_type, png::ColorType::Rgba);
+    assert!(frame.chunks_exact(4).zip(image.pixels()).all(|(frame, pixel)| frame[3] == pixel[3]));
+
+    Ok(())
+}
+
+#[test]
+fn animated_webp_output_is_rejected() -> std::io::Result<()> {
+    let output = self::run(&["--use-default-base", "--format", "awebp", "--output-dir", "unused"])?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("animated webp files cannot be encoded yet"), "{stderr}");
+
+    Ok(())
+}
+
+#[test]
+fn gamma_is_stored_without_changing_pixels() -> Result<(), Box<dyn std::error::Error>> {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("gamma");
+    let config = directory.join("config.json");
+
+    std::fs::create_dir_all(&directory)?;
+
+    for depth in [8, 16] {
+        let color = r#""purple": { "rgb": [137, 50, 184] }"#;
+
+        std::fs::write(&config, format!(r#"{{ "output_depth": {depth}, "colors": {{ {color} }} }}"#))?;
+
+        let config = config.to_string_lossy();
+        let [plain, gamma] = ["plain", "gamma"].map(|name| directory.join(format!("{depth}_{name}")));
+        let [plain_path, gamma_path] = [&plain, &gamma].map(|path| path.to_string_lossy());
+
+        for arguments in [&["--output-dir", &plain_path][..], &["--output-dir", &gamma_path, "--gamma", "0.45455"]] {
+            let output = self::run(&[&["--use-default-base", "--config", &config], arguments].concat())?;
+
+            assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        }
+
+        let [plain_path, gamma_path] = [plain, gamma].map(|path| path.join("purple_amethyst.png"));
+        let reader = png::Decoder::new(std::fs::File::open(&gamma_path)?).read_info()?;
+
+        assert_eq!(reader.info().source_gamma, Some(png::ScaledFloat::new(0.45455)));
+        assert_eq!(image::open(plain_path)?, image::open(gamma_path)?);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn outputs_can_be_grouped_by_color() -> Result<(), Box<dyn std::error::Error>> {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("grouped_outputs");
+    let config = self::resource("res/default.json");
+    let output = self::run(&[
+        "--use-default-base",
+        "--group-by-color",
+        "--config",
+        &config.to_string_lossy(),
+        "--output-dir",
+        &directory.to_string_lossy(),
+    ])?;
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    for color in ["purple", "light_gray"] {
+        assert!(directory.join(color).join(format!("{color}_amethyst.png")).is_file(), "{color} is missing");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn json_errors_describe_library_failures() -> Result<(), Box<dyn std::error::Error>> {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("library_errors");
+    let config = directory.join("config.json");
+
+    std::fs::create_dir_all(&directory)?;
+    std::fs::write(&config, r#"{ "colors": { "gray": { "rgb": [128, 128, 128] } } }"#)?;
+
+    let output = self::run(&["--use-default-base", "--json-errors", "--config", &config.to_string_lossy()])?;
+    let error: serde_json::Value = serde_json::from_slice(&output.stderr)?;
+
+    assert!(!output.status.success());
+    assert_eq!(error["code"], "achromatic_color");
+    assert_eq!(error["context"], serde_json::json!({ "color": "gray", "rgb": [128, 128, 128] }));
+
+    Ok(())
+}
+
+#[test]
+fn regions_limit_the_converted_area() -> Result<(), Box<dyn std::error::Error>> {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("regions");
+    let config = self::resource("res/default.json");
+    let (config, destination) = (config.to_string_lossy(), directory.to_string_lossy());
+    let arguments = ["--use-default-base", "--target-color", "purple", "--config", &config, "-o", &destination];
+    let output = self::run(&[&arguments[..], &["--region", "0,0,8,16"]].concat())?;
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let source = image::open(self::resource("res/amethyst.png"))?.to_rgba8();
+    let converted = image::open(directory.join("purple_amethyst.png"))?.to_rgba8();
+    let unchanged = |x: u32, y: u32| source.get_pixel(x, y) == converted.get_pixel(x, y);
+
+    assert!((8 .. 16).all(|x| (0 .. 16).all(|y| unchanged(x, y))));
+    assert!(!(0 .. 8).all(|x| (0 .. 16).all(|y| unchanged(x, y))));
+
+    let output = self::run(&[&arguments[..], &["--region", "8,8,16,16"]].concat())?;
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("is outside of the 16x16 image"));
+
+    Ok(())
+}
+
+#[test]
+fn config_differences_are_semantic() -> Result<(), Box<dyn std::error::Error>> {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("config_differences");
+    let [old, new, reordered] = ["old", "new", "reordered"].map(|name| directory.join(format!("{name}.json")));
+
+    std::fs::create_dir_all(&directory)?;
+    let (purple, red) = (r#""purple": { "rgb": [137, 50, 184] }"#, r#""red": { "rgb": [176, 46, 38] }"#);
+    let (changed, blue) = (r#""purple": { "rgb": [140, 50, 184] }"#, r#""blue": { "rgb": [60, 68, 170] }"#);
+
+    std::fs::write(&old, format!(r#"{{ "colors": {{ {purple}, {red} }} }}"#))?;
+    std::fs::write(&new, format!(r#"{{ "colors": {{ {changed}, {blue} }} }}"#))?;
+    std::fs::write(&reordered, format!(r#"{{ "colors": {{ {red}, {purple} }} }}"#))?;
+
+    let output = self::run(&["diff-config", &old.to_string_lossy(), &new.to_string_lossy()])?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(stdout.contains("- red") && stdout.contains("+ blue"), "{stdout}");
+    assert!(stdout.contains("~ purple\n    rgb: [137, 50, 184] -> [140, 50, 184]"), "{stdout}");
+
+    let output = self::run(&["diff-config", &old.to_string_lossy(), &reordered.to_string_lossy()])?;
+
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "no differences");
+
+    Ok(())
+}
+
+#[test]
+fn repeated_runs_replace_outputs_atomically() -> Result<(), Box<dyn std::error::Error>> {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("atomic_outputs");
+    let config = self::resource("res/default.json");
+    let arguments = ["--use-default-base", "--config", &config.to_string_lossy(), "-o", &directory.to_string_lossy()];
+    let mut runs = Vec::with_capacity(2);
+
+    for _ in 0 .. 2 {
+        let output = self::run(&arguments)?;
+
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+        let mut files = std::fs::read_dir(&directory)?
+            .map(|entry| entry.and_then(|entry| Ok((entry.file_name(), std::fs::read(entry.path())?))))
+            .collect::<std::io::Result<Vec<_>>>()?;
+
+        files.sort();
+
+        assert!(files.iter().all(|(name, _)| !name.to_string_lossy().ends_with(".tmp")));
+
+        runs.push(files);
+    }
+
+    assert_eq!(runs[0], runs[1]);
+
+    Ok(())
+}
+
+#[test]
+fn output_depth_sets_the_bits_per_channel() -> Result<(), Box<dyn std::error::Error>> {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("output_depth");
+    let config = directory.join("config.json");
+
+    std::fs::create_dir_all(&directory)?;
+
+    for (depth, expected) in [("8", image::ColorType::Rgba8), ("16", image::ColorType::Rgba16)] {
+        let (output, color) = (directory.join(depth), r#""purple": { "rgb": [137, 50, 184] }"#);
+
+        std::fs::write(&config, format!(r#"{{ "output_depth": {depth}, "colors": {{ {color} }} }}"#))?;
+
+        let (path, destination) = (config.to_string_lossy(), output.to_string_lossy());
+        let result = self::run(&["--use-default-base", "--config", &path, "-o", &destination])?;
+
+        assert!(result.status.success(), "{}", String::from_utf8_lossy(&result.stderr));
+        assert_eq!(image::open(output.join("purple_amethyst.png"))?.color(), expected);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn archives_contain_every_output() -> Result<(), Box<dyn std::error::Error>> {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("archives");
+    let config = self::resource("res/default.json");
+    let (config, destination) = (config.to_string_lossy(), directory.to_string_lossy());
+
+    for format in ["zip", "targz"] {
+        let arguments = ["--use-default-base", "--config", &config, "-o", &destination, "--output-format", format];
+        let output = self::run(&arguments)?;
+
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    let zip = zip::ZipArchive::new(std::fs::File::open(directory.join("amethyst.zip"))?)?;
+    let mut zipped = zip.file_names().map(str::to_string).collect::<Vec<_>>();
+    let tarball = flate2::read::GzDecoder::new(std::fs::File::open(directory.join("amethyst.tar.gz"))?);
+    let mut tarball = tar::Archive::new(tarball);
+    let mut tarred = tarball
+        .entries()?
+        .map(|entry| entry.and_then(|entry| Ok(entry.path()?.to_string_lossy().into_owned())))
+        .collect::<std::io::Result<Vec<_>>>()?;
+
+    zipped.sort();
+    tarred.sort();
+
+    assert_eq!(zipped.len(), 16);
+    assert!(zipped.contains(&"purple_amethyst.png".to_string()));
+    assert_eq!(zipped, tarred);
+
+    Ok(())
+}
+
+#[test]
+fn summaries_describe_every_output() -> Result<(), Box<dyn std::error::Error>> {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("summaries");
+    let (config, summary) = (self::resource("res/default.json"), directory.join("summary.json"));
+    let (config, path) = (config.to_string_lossy(), summary.to_string_lossy());
+    let destination = directory.to_string_lossy();
+    let output = self::run(&["--use-default-base", "--config", &config, "-o", &destination, "--summary-json", &path])?;
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let summary: serde_json::Value = serde_json::from_slice(&std::fs::read(&summary)?)?;
+    let outputs = summary["outputs"].as_array().map(Vec::as_slice).unwrap_or_default();
+    let written = summary["written"].as_array().map(Vec::as_slice).unwrap_or_default();
+
+    assert_eq!(summary["version"], 1);
+    assert_eq!(outputs.len(), 16);
+    assert_eq!(written.len(), 16);
+
+    for output in outputs {
+        let entry = output["entry"].as_str().unwrap_or_default();
+
+        assert!(written.iter().any(|path| path.as_str().is_some_and(|path| path.ends_with(entry))), "{entry}");
+        assert_eq!(entry, format!("{}_amethyst.png", output["color"].as_str().unwrap_or_default()));
+        assert_eq!((output["width"].as_u64(), output["height"].as_u64()), (Some(16), Some(16)));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn stats_count_images_and_pixels() -> Result<(), Box<dyn std::error::Error>> {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("stats");
+    let job = directory.join("job.json");
+    let config = self::resource("res/default.json");
+    let entry =
+        |input: &str| serde_json::json!({ "input": input, "config": config, "output": "out", "options": ["--stats"] });
+
+    std::fs::create_dir_all(&directory)?;
+    std::fs::copy(self::resource("res/amethyst.png"), directory.join("large.png"))?;
+    image::RgbaImage::from_pixel(8, 4, image::Rgba([120, 60, 200, 255])).save(directory.join("small.png"))?;
+    std::fs::write(&job, serde_json::json!({ "entries": [entry("large.png"), entry("small.png")] }).to_string())?;
+
+    let output = self::run(&["job", &job.to_string_lossy()])?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let stats = |name: &str| -> Vec<u64> {
+        let values = stderr.lines().filter_map(|line| line.strip_prefix(name)?.strip_prefix(": "));
+
+        values.filter_map(|value| value.parse().ok()).collect()
+    };
+
+    assert!(output.status.success(), "{stderr}");
+    assert_eq!(stats("images"), [16, 16]);
+    assert_eq!(stats("pixels"), [16 * 256, 16 * 32]);
+    assert_eq!(stats("pixels").iter().sum::<u64>(), 4608);
+
+    let rates = stderr.lines().filter_map(|line| line.strip_prefix("images/sec: "));
+
+    assert_eq!(rates.filter(|rate| rate.parse::<f64>().is_ok_and(|rate| rate > 0.0)).count(), 2, "{stderr}");
+
+    Ok(())
+}
+
+#[test]
+fn unchanged_outputs_can_be_skipped() -> Result<(), Box<dyn std::error::Error>> {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("unchanged_outputs");
+    let (input, config) = (directory.join("gray.png"), directory.join("config.json"));
+    let summary = directory.join("summary.json");
+    let purple = r#""purple": { "rgb": [137, 50, 184] }"#;
+    let filter = r#"{ "type": "image", "target": "brightness", "operation": "add", "value": 10.0 }"#;
+    let red = format!(r#""red": {{ "rgb": [176, 46, 38], "filters": [{filter}] }}"#);
+
+    std::fs::create_dir_all(&directory)?;
+    std::fs::write(&config, format!(r#"{{ "colors": {{ {purple}, {red} }} }}"#))?;
+    image::GrayImage::from_fn(4, 4, |x, y| image::Luma([u8::try_from(x * 40 + y).unwrap_or(0)])).save(&input)?;
+
+    let output = self::run(&[
+        &input.to_string_lossy(),
+        "--skip-unchanged",
+        "--config",
+        &config.to_string_lossy(),
+        "--summary-json",
+        &summary.to_string_lossy(),
+        "-o",
+        &directory.join("out").to_string_lossy(),
+    ])?;
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let summary: serde_json::Value = serde_json::from_slice(&std::fs::read(summary)?)?;
+
+    assert_eq!(summary["skipped"], serde_json::json!(["purple"]));
+    assert!(!directory.join("out").join("purple_amethyst.png").exists());
+    assert!(directory.join("out").join("red_amethyst.png").is_file());
+
+    Ok(())
+}
+
+#[test]
+fn force_rgb_blends_every_color() -> Result<(), Box<dyn std::error::Error>> {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("force_rgb");
+    let (input, config) = (directory.join("input.png"), directory.join("config.json"));
+
+    std::fs::create_dir_all(&directory)?;
+    std::fs::write(&config, r#"{ "colors": { "purple": { "rgb": [137, 50, 184] } } }"#)?;
+    image::RgbaImage::from_fn(4, 4, |x, y| {
+        image::Rgba([u8::try_from(x * 60).unwrap_or(0), 90, 20, u8::try_from(y * 80).unwrap_or(0)])
+    })
+    .save(&input)?;
+
+    for (factor, succeeds) in [("1.0", true), ("1.5", false)] {
+        let output = directory.join(format!("out_{factor}"));
+        let result = self::run(&[
+            &input.to_string_lossy(),
+            "--force-rgb",
+            factor,
+            "--config",
+            &config.to_string_lossy(),
+            "-o",
+            &output.to_string_lossy(),
+        ])?;
+
+        assert_eq!(result.status.success(), succeeds, "{}", String::from_utf8_lossy(&result.stderr));
+
+        if succeeds {
+            let image = image::open(output.join("purple_amethyst.png"))?.into_rgba8();
+
+            for (_, y, pixel) in image.enumerate_pixels() {
+                assert_eq!(pixel.0, [137, 50, 184, u8::try_from(y * 80).unwrap_or(0)]);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn set_color_overrides_configured_colors() -> std::io::Result<()> {
+    let config = self::resource("res/default.json");
+    let config = config.to_string_lossy();
+    let output = self::run(&["--print-effective-hue", "--config", &config, "--set-color", " Purple = 00ff00 "])?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let purple = stdout.lines().find(|l| l.starts_with("purple ")).unwrap_or_default();
+
+    assert!(purple.contains("#00FF00") && purple.contains("120.00"), "{stdout}");
+
+    for invalid in ["purple", "purple=#0f0", "purple=#00gg00", "amethyst=#00ff00"] {
+        let output = self::run(&["--print-effective-hue", "--config", &config, "--set-color", invalid])?;
+
+        assert!(!output.status.success(), "'{invalid}' should be rejected");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn tga_images_can_be_read_and_written() -> Result<(), Box<dyn std::error::Error>> {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("tga_images");
+    let input = directory.join("amethyst.tga");
+    let config = self::resource("res/default.json");
+
+    std::fs::create_dir_all(&directory)?;
+    image::open(self::resource("res/amethyst.png"))?.save(&input)?;
+
+    for format in ["png", "tga"] {
+        let result = self::run(&[
+            &input.to_string_lossy(),
+            "--config",
+            &config.to_string_lossy(),
+            "-t",
+            "purple",
+            "--format",
+            format,
+            "-o",
+            &directory.join("out").to_string_lossy(),
+        ])?;
+
+        assert!(result.status.success(), "{}", String::from_utf8_lossy(&result.stderr));
+    }
+
+    let png = image::open(directory.join("out").join("purple_amethyst.png"))?;
+    let tga = image::open(directory.join("out").join("purple_amethyst.tga"))?;
+
+    assert_eq!(tga.to_rgba8(), png.to_rgba8());
+
+    Ok(())
+}
+
+#[test]
+fn mipmaps_are_written_for_each_output() -> Result<(), Box<dyn std::error::Error>> {
+    let output = Path::new(env!("CARGO_TARGET_TMPDIR")).join("mipmaps");
+    let config = self::resource("res/default.json");
+    let result = self::run(&[
+        &self::resource("res/amethyst.png").to_string_lossy(),
+        "--mipmaps",
+        "--config",
+        &config.to_string_lossy(),
+        "-t",
+        "purple",
+        "-o",
+        &output.to_string_lossy(),
+    ])?;
+
+    assert!(result.status.success(), "{}", String::from_utf8_lossy(&result.stderr));
+
+    for (level, size) in [(1, 8), (2, 4), (3, 2), (4, 1)] {
+        let mipmap = image::open(output.join(format!("purple_amethyst_mip{level}.png")))?;
+
+        assert_eq!((mipmap.width(), mipmap.height()), (size, size));
+    }
+
+    assert!(!output.join("purple_amethyst_mip5.png").exists());
+
+    Ok(())
+}
+
+#[test]
+fn seeds_override_every_noise_filter() -> Result<(), Box<dyn std::error::Error>> {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("seeds");
+    let config = directory.join("config.json");
+    let noise = r#"{ "type": "image", "target": "noise", "operation": "add", "value": 0.2, "seed": 1 }"#;
+    let purple = format!(r#""purple": {{ "rgb": [137, 50, 184], "filters": [{noise}] }}"#);
+
+    std::fs::create_dir_all(&directory)?;
+    std::fs::write(&config, format!(r#"{{ "colors": {{ {purple} }} }}"#))?;
+
+    let convert = |seed: Option<&str>| -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let output = directory.join(format!("out_{}", seed.unwrap_or("none")));
+        let (config, output_dir) = (config.to_string_lossy(), output.to_string_lossy());
+        let mut arguments = vec!["--use-default-base", "--config", &config, "-o", &output_dir];
+
+        arguments.extend(seed.iter().flat_map(|seed| ["--seed", seed]));
+
+        let result = self::run(&arguments)?;
+
+        assert!(result.status.success(), "{}", String::from_utf8_lossy(&result.stderr));
+
+        Ok(image::open(output.join("purple_amethyst.png"))?.into_bytes())
+    };
+
+    assert_eq!(convert(Some("1"))?, convert(None)?);
+    assert_ne!(convert(Some("2"))?, convert(None)?);
+
+    Ok(())
+}
+
+#[test]
+fn seed_palettes_write_one_output_per_hue() -> Result<(), Box<dyn std::error::Error>> {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("seed_palettes");
+    let config = self::resource("res/default.json");
+    let (config, output) = (config.to_string_lossy(), directory.join("even"));
+    let arguments = ["--use-default-base", "--config", &config, "--seed-palette", "even"];
+    let result = self::run(&[&arguments[..], &["--palette-count", "12", "-o", &output.to_string_lossy()]].concat())?;
+
+    assert!(result.status.success(), "{}", String::from_utf8_lossy(&result.stderr));
+
+    let names = std::fs::read_dir(&output)?.map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()));
+    let mut names = names.collect::<std::io::Result<Vec<_>>>()?;
+
+    names.sort();
+
+    let expected = (0 .. 12).map(|index| format!("hue_{:03}_amethyst.png", index * 30)).collect::<Vec<_>>();
+
+    assert_eq!(names, expected);
+
+    // Hues that round to the same degree would share a name, so they are rejected before anything is written.
+    let output = directory.join("crowded");
+    let result = self::run(&[&arguments[..], &["--palette-count", "720", "-o", &output.to_string_lossy()]].concat())?;
+    let stderr = String::from_utf8_lossy(&result.stderr);
+
+    assert!(!result.status.success());
+    assert!(stderr.contains("would both be written using the name 'hue_001'"), "{stderr}");
+    assert!(std::fs::read_dir(&output).map_or(true, |mut entries| entries.next().is_none()));
+
+    Ok(())
+}
+
+#[test]
+fn linear_inputs_round_trip_through_identity_configs() -> Result<(), Box<dyn std::error::Error>> {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("linear_round_trip");
+    let input = directory.join("linear.png");
+    let config = directory.join("config.json");
+
+    std::fs::create_dir_all(&directory)?;
+    std::fs::write(&config, r#"{ "colors": { "orange": { "rgb": [255, 128, 0], "filters": [] } } }"#)?;
+
+    // Shades of the target color after linearization, along with a gray whose hue has no effect.
+    let pixels = [[255, 55, 0, 255], [128, 28, 0, 255], [64, 14, 0, 200], [90, 90, 90, 255]];
+    let source = image::RgbaImage::from_fn(4, 1, |x, _| image::Rgba(pixels[x as usize]));
+
+    source.save(&input)?;
+
+    for assume_linear in [true, false] {
+        let output = directory.join(format!("out_{assume_linear}"));
+        let mut arguments = vec![
+            input.to_string_lossy().into_owned(),
+            "--config".to_string(),
+            config.to_string_lossy().into_owned(),
+            "--target-color".to_string(),
+            "orange".to_string(),
+            "--output-dir".to_string(),
+            output.to_string_lossy().into_owned(),
+        ];
+
+        if assume_linear {
+            arguments.push("--assume-linear".to_string());
+        }
+
+        let arguments: Vec<&str> = arguments.iter().map(String::as_str).collect();
+        let result = self::run(&arguments)?;
+
+        assert!(result.status.success(), "{}", String::from_utf8_lossy(&result.stderr));
+
+        let converted = image::open(output.join("orange_amethyst.png"))?.into_rgba8();
+
+        assert_eq!(converted == source, assume_linear);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn stages_are_dumped_after_the_hue_and_each_filter() -> Result<(), Box<dyn std::error::Error>> {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("dump_stages");
+    let config = directory.join("config.json");
+    let stages = directory.join("stages");
+
+    std::fs::create_dir_all(&directory)?;
+
+    let filters = [
+        r#"{ "type": "image", "target": "contrast", "operation": "add", "value": 10.0 }"#,
+        r#"{ "type": "pixel", "target": "saturation", "operation": "multiply", "value": 0.5 }"#,
+    ];
+    let color = format!(r#"{{ "rgb": [137, 50, 184], "filters": [{}] }}"#, filters.join(", "));
+
+    std::fs::write(&config, format!(r#"{{ "colors": {{ "purple": {color} }} }}"#))?;
+
+    let output = self::run(&[
+        &self::resource("res/amethyst.png").to_string_lossy(),
+        "--config",
+        &config.to_string_lossy(),
+        "--target-color",
+        "purple",
+        "--output-dir",
+        &directory.join("out").to_string_lossy(),
+        "--dump-stages",
+        &stages.to_string_lossy(),
+    ])?;
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let mut names = std::fs::read_dir(&stages)?
+        .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+
+    names.sort();
+
+    // Pixel filters are applied before image filters, regardless of their declared order.
+    assert_eq!(names.len(), filters.len() + 1);
+    assert_eq!(names, ["00_hue.png", "01_saturation.png", "02_contrast.png"]);
+
+    Ok(())
+}
+
+#[test]
+fn unexpected_sizes_are_rejected() -> std::io::Result<()> {
+    let input = self::resource("res/amethyst.png");
+    let config = self::resource("res/default.json");
+    let output = Path::new(env!("CARGO_TARGET_TMPDIR")).join("expect_size");
+
+    for (size, expected) in [("16x16", true), ("32x16", false)] {
+        let result = self::run(&[
+            &input.to_string_lossy(),
+            "--config",
+            &config.to_string_lossy(),
+            "--target-color",
+            "purple",
+            "--output-dir",
+            &output.to_string_lossy(),
+            "--expect-size",
+            size,
+        ])?;
+        let stderr = String::from_utf8_lossy(&result.stderr);
+
+        assert_eq!(result.status.success(), expected, "{stderr}");
+
+        if !expected {
+            let message = format!("expected {} to be 32x16, but it is 16x16", input.display());
+
+            assert!(stderr.contains(&message), "{stderr}");
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn forced_input_formats_ignore_the_extension() -> Result<(), Box<dyn std::error::Error>> {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("input_format");
+    let input = directory.join("amethyst.dat");
+    let config = self::resource("res/default.json");
+
+    std::fs::create_dir_all(&directory)?;
+    std::fs::copy(self::resource("res/amethyst.png"), &input)?;
+
+    let convert = |format: Option<&str>, output: &str| {
+        let output = directory.join(output);
+        let mut arguments = vec![
+            input.to_string_lossy().into_owned(),
+            "--config".to_string(),
+            config.to_string_lossy().into_owned(),
+            "--target-color".to_string(),
+            "purple".to_string(),
+            "--output-dir".to_string(),
+            output.to_string_lossy().into_owned(),
+        ];
+
+        if let Some(format) = format {
+            arguments.extend(["--input-format".to_string(), format.to_string()]);
+        }
+
+        self::run(&arguments.iter().map(String::as_str).collect::<Vec<_>>())
+    };
+
+    let unknown = convert(None, "unknown")?;
+
+    assert!(!unknown.status.success());
+    assert!(String::from_utf8_lossy(&unknown.stderr).contains("must be either a .zip or image file"));
+
+    let mismatched = convert(Some("tga"), "tga")?;
+
+    assert!(!mismatched.status.success());
+    assert!(String::from_utf8_lossy(&mismatched.stderr).contains("as Tga"));
+
+    let forced = convert(Some("png"), "png")?;
+
+    assert!(forced.status.success(), "{}", String::from_utf8_lossy(&forced.stderr));
+
+    let expected = directory.join("expected");
+    let result = self::run(&[
+        &self::resource("res/amethyst.png").to_string_lossy(),
+        "--config",
+        &config.to_string_lossy(),
+        "--target-color",
+        "purple",
+        "--output-dir",
+        &expected.to_string_lossy(),
+    ])?;
+
+    assert!(result.status.success(), "{}", String::from_utf8_lossy(&result.stderr));
+    assert_eq!(
+        image::open(directory.join("png").join("purple_amethyst.png"))?,
+        image::open(expected.join("purple_amethyst.png"))?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn jobs_convert_every_entry() -> Result<(), Box<dyn std::error::Error>> {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("job_entries");
+    let job = directory.join("job.json");
+    let config = self::resource("res/default.json");
+    let input = self::resource("res/amethyst.png");
+    let entry = |color: &str| serde_json::json!({ "input": input, "config": config, "output": color, "color": color });
+
+    if directory.try_exists()? {
+        std::fs::remove_dir_all(&directory)?;
+    }
+
+    std::fs::create_dir_all(&directory)?;
+    std::fs::write(&job, serde_json::json!({ "entries": [entry("purple"), entry("red")] }).to_string())?;
+
+    let output = self::run(&["job", &job.to_string_lossy()])?;
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    for color in ["purple", "red"] {
+        let written = std::fs::read_dir(directory.join(color))?
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+
+        assert_eq!(written, [format!("{color}_amethyst.png")]);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn filters_can_be_applied_without_replacing_the_hue() -> Result<(), Box<dyn std::error::Error>> {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("no_hue");
+    let (input, config) = (directory.join("red.png"), directory.join("config.json"));
+    let filter = r#"{ "type": "pixel", "target": "brightness", "operation": "multiply", "value": 0.5 }"#;
+
+    std::fs::create_dir_all(&directory)?;
+    image::RgbaImage::from_pixel(4, 4, image::Rgba([200, 40, 120, 255])).save(&input)?;
+    std::fs::write(
+        &config,
+        format!(r#"{{ "colors": {{ "purple": {{ "rgb": [137, 50, 184], "filters": [{filter}] }} }} }}"#),
+    )?;
+
+    let convert = |no_hue: bool| -> Result<image::Rgba<u8>, Box<dyn std::error::Error>> {
+        let output = directory.join(format!("out_{no_hue}"));
+        let mut arguments = vec![
+            input.to_string_lossy().into_owned(),
+            "--config".to_string(),
+            config.to_string_lossy().into_owned(),
+            "--target-color".to_string(),
+            "purple".to_string(),
+            "--output-dir".to_string(),
+            output.to_string_lossy().into_owned(),
+        ];
+
+        if no_hue {
+            arguments.push("--no-hue".to_string());
+        }
+
+        let result = self::run(&arguments.iter().map(String::as_str).collect::<Vec<_>>())?;
+
+        assert!(result.status.success(), "{}", String::from_utf8_lossy(&result.stderr));
+
+        Ok(*image::open(output.join("purple_amethyst.png"))?.into_rgba8().get_pixel(0, 0))
+    };
+
+    // Halving the brightness halves every channel, leaving the original hue and saturation as-is.
+    let dimmed = convert(true)?;
+
+    assert!(
+        dimmed.0.iter().zip([100, 20, 60, 255]).all(|(actual, expected)| actual.abs_diff(expected) <= 1),
+        "{dimmed:?}"
+    );
+    assert_ne!(convert(false)?, dimmed);
+
+    Ok(())
+}
+
+#[test]
+fn split_channels_match_the_output() -> Result<(), Box<dyn std::error::Error>> {
+    let output = Path::new(env!("CARGO_TARGET_TMPDIR")).join("split_channels");
+    let result = self::run(&[
+        &self::resource("res/amethyst.png").to_string_lossy(),
+        "--config",
+        &self::resource("res/default.json").to_string_lossy(),
+        "--target-color",
+        "purple",
+        "--output-dir",
+        &output.to_string_lossy(),
+        "--split-channels",
+    ])?;
+
+    assert!(result.status.success(), "{}", String::from_utf8_lossy(&result.stderr));
+
+    let converted = image::open(output.join("purple_amethyst.png"))?.into_rgba8();
+
+    for (index, channel) in ["r", "g", "b", "a"].into_iter().enumerate() {
+        let split = image::open(output.join(format!("purple_amethyst_{channel}.png")))?;
+        let expected = converted.pixels().map(|pixel| pixel.0[index]).collect::<Vec<_>>();
+
+        assert_eq!(split.color(), image::ColorType::L8);
+        assert_eq!((split.width(), split.height()), converted.dimensions());
+        assert_eq!(split.into_bytes(), expected, "{channel}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn color_maps_list_every_configured_color() -> Result<(), Box<dyn std::error::Error>> {
+    let config = self::resource("res/default.json");
+    let colors: serde_json::Value = serde_json::from_slice(&std::fs::read(&config)?)?;
+    let expected = colors["colors"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(color, config)| {
+            let channel = |index: usize| config["rgb"][index].as_u64().unwrap_or_default();
+
+            (color.clone(), format!("#{:02X}{:02X}{:02X}", channel(0), channel(1), channel(2)))
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(expected.len(), 16);
+
+    let json = self::run(&["color-map", &config.to_string_lossy(), "--format", "json"])?;
+    let css = self::run(&["color-map", &config.to_string_lossy()])?;
+
+    assert!(json.status.success(), "{}", String::from_utf8_lossy(&json.stderr));
+    assert!(css.status.success(), "{}", String::from_utf8_lossy(&css.stderr));
+
+    let map: serde_json::Map<String, serde_json::Value> = serde_json::from_slice(&json.stdout)?;
+    let css = String::from_utf8_lossy(&css.stdout);
+
+    assert_eq!(map.len(), expected.len());
+
+    for (color, hex) in expected {
+        assert_eq!(map[&color], hex.as_str());
+        assert!(css.contains(&format!("--dye-{}: {hex};", color.replace('_', "-"))), "{css}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn jobs_can_continue_past_failed_entries() -> Result<(), Box<dyn std::error::Error>> {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("continue_on_error");
+    let job = directory.join("job.json");
+    let config = self::resource("res/default.json");
+
+    if directory.try_exists()? {
+        std::fs::remove_dir_all(&directory)?;
+    }
+
+    std::fs::create_dir_all(&directory)?;
+    std::fs::copy(self::resource("res/amethyst.png"), directory.join("first.png"))?;
+    std::fs::copy(self::resource("res/amethyst.png"), directory.join("third.png"))?;
+    std::fs::write(directory.join("corrupt.png"), b"not a png")?;
+
+    let entries = ["first", "corrupt", "third"].map(|name| {
+        let input = format!("{name}.png");
+
+        serde_json::json!({ "input": input, "config": config, "output": name, "color": "purple" })
+    });
+
+    std::fs::write(&job, serde_json::json!({ "entries": entries }).to_string())?;
+
+    let stopped = self::run(&["job", &job.to_string_lossy()])?;
+
+    assert!(!stopped.status.success());
+    assert!(directory.join("first/purple_amethyst.png").try_exists()?);
+    assert!(!directory.join("third").try_exists()?);
+
+    let output = self::run(&["job", &job.to_string_lossy(), "--continue-on-error"])?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success(), "{stderr}");
+    assert!(stderr.contains("failed to convert") && stderr.contains("corrupt.png"), "{stderr}");
+    assert!(stderr.contains("1 of 3 job entries failed"), "{stderr}");
+    assert!(directory.join("first/purple_amethyst.png").try_exists()?);
+    assert!(directory.join("third/purple_amethyst.png").try_exists()?);
+    assert!(!directory.join("corrupt/purple_amethyst.png").try_exists()?);
+
+    Ok(())
+}
+
+#[test]
+fn companion_textures_are_copied_as_is() -> Result<(), Box<dyn std::error::Error>> {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("companion_textures");
+    let (input, output) = (directory.join("amethyst.png"), directory.join("out"));
+    let normal = [0x89, b'P', b'N', b'G', 0, 1, 2, 3, 255];
+
+    if directory.try_exists()? {
+        std::fs::remove_dir_all(&directory)?;
+    }
+
+    std::fs::create_dir_all(&directory)?;
+    std::fs::copy(self::resource("res/amethyst.png"), &input)?;
+    std::fs::write(directory.join("amethyst_n.png"), normal)?;
+
+    let result = self::run(&[
+        &input.to_string_lossy(),
+        "--config",
+        &self::resource("res/default.json").to_string_lossy(),
+        "--output-dir",
+        &output.to_string_lossy(),
+    ])?;
+
+    assert!(result.status.success(), "{}", String::from_utf8_lossy(&result.stderr));
+
+    for color in ["purple", "red", "white"] {
+        assert!(output.join(format!("{color}_amethyst.png")).try_exists()?);
+        assert_eq!(std::fs::read(output.join(format!("{color}_amethyst_n.png")))?, normal);
+        assert!(!output.join(format!("{color}_amethyst_s.png")).try_exists()?);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn palettes_are_sampled_from_resource_packs() -> Result<(), Box<dyn std::error::Error>> {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("palette_from_pack");
+    let (pack, output) = (directory.join("pack"), directory.join("palette.json"));
+    let textures = pack.join("assets/minecraft/textures/block");
+
+    if directory.try_exists()? {
+        std::fs::remove_dir_all(&directory)?;
+    }
+
+    std::fs::create_dir_all(&textures)?;
+
+    for (color, rgb) in [("red", [176, 46, 38]), ("blue", [60, 68, 170])] {
+        let texture = image::RgbaImage::from_pixel(4, 4, image::Rgba([rgb[0], rgb[1], rgb[2], 255]));
+
+        texture.save(textures.join(format!("{color}_wool.png")))?;
+    }
+
+    let result = self::run(&["palette-from-pack", &pack.to_string_lossy(), "-o", &output.to_string_lossy()])?;
+    let stderr = String::from_utf8_lossy(&result.stderr);
+
+    assert!(result.status.success(), "{stderr}");
+    assert!(stderr.contains("skipped color 'white'"), "{stderr}");
+
+    let palette: serde_json::Value = serde_json::from_slice(&std::fs::read(&output)?)?;
+
+    assert_eq!(palette["colors"].as_object().map(serde_json::Map::len), Some(2));
+    assert_eq!(palette["colors"]["red"]["rgb"], serde_json::json!([176, 46, 38]));
+    assert_eq!(palette["colors"]["blue"]["rgb"], serde_json::json!([60, 68, 170]));
+
+    Ok(())
+}